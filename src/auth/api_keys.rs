@@ -0,0 +1,278 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::utils::hex;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::error;
+
+use crate::config::ApiKeyConfig;
+
+const KEY_PREFIX: &str = "wm_";
+
+/// What a key is allowed to do. Each route requires exactly one scope.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Read-only token queries such as `/token/balanceOf`
+    Read,
+    /// Building unsigned transactions in `/token` and `/wallet`
+    Build,
+    /// Broadcasting signed transactions
+    Broadcast,
+    /// Server-side signing with a private key
+    Sign,
+    /// Issuing and revoking keys
+    Admin,
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_lowercase()))
+            .map_err(|_| format!("unknown scope {s:?}, expected read, build, broadcast, sign or admin"))
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub name: String,
+    /// hex-encoded sha256 of the key, the key itself is never stored
+    pub key_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+/// The authenticated caller, stored in the request extensions.
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub(super) fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    hex::encode(buf)
+}
+
+/// Runtime keys as last read from the keys file, with the file's modification time and length.
+struct Snapshot {
+    stamp: Option<(SystemTime, u64)>,
+    keys: Vec<ApiKeyRecord>,
+}
+
+fn file_stamp(path: &Path) -> io::Result<Option<(SystemTime, u64)>> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some((metadata.modified()?, metadata.len()))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn read_keys(path: &Path) -> Result<Vec<ApiKeyRecord>, Box<dyn std::error::Error>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Keys from the config file (read-only) plus keys issued at runtime, persisted to a JSON file.
+///
+/// The file is the source of truth, shared with `backend keys` in other processes: it is re-read
+/// whenever it changed before keys are checked or listed, and every change re-reads and rewrites
+/// it under an exclusive lock on `<keys file>.lock`.
+pub struct ApiKeyStore {
+    path: PathBuf,
+    static_keys: Vec<ApiKeyRecord>,
+    snapshot: RwLock<Snapshot>,
+}
+
+impl ApiKeyStore {
+    pub fn load(path: &Path, static_keys: &[ApiKeyConfig]) -> Result<Self, Box<dyn std::error::Error>> {
+        let stamp = file_stamp(path)?;
+        let keys = read_keys(path)?;
+        let static_keys = static_keys
+            .iter()
+            .map(|key| ApiKeyRecord {
+                id: format!("config:{}", key.name),
+                name: key.name.clone(),
+                key_hash: key.key_hash.to_lowercase(),
+                scopes: key.scopes.clone(),
+                created_at: 0,
+                revoked: false,
+            })
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            static_keys,
+            snapshot: RwLock::new(Snapshot { stamp, keys }),
+        })
+    }
+
+    /// Re-reads the keys file when it changed since the last read. A file that can't be read
+    /// keeps the previous keys.
+    fn refresh(&self) {
+        let stamp = match file_stamp(&self.path) {
+            Ok(stamp) => stamp,
+            Err(err) => {
+                error!(error = %err, path = ?self.path, "Failed to check the API keys file");
+                return;
+            },
+        };
+        if self.snapshot.read().unwrap().stamp == stamp {
+            return;
+        }
+        match read_keys(&self.path) {
+            Ok(keys) => *self.snapshot.write().unwrap() = Snapshot { stamp, keys },
+            Err(err) => error!(error = %err, path = ?self.path, "Failed to reload the API keys file"),
+        }
+    }
+
+    pub fn authenticate(&self, key: &str) -> Option<ApiClient> {
+        self.refresh();
+        let key_hash = hash_key(key);
+        let snapshot = self.snapshot.read().unwrap();
+        self.static_keys
+            .iter()
+            .chain(snapshot.keys.iter())
+            .find(|record| !record.revoked && record.key_hash == key_hash)
+            .map(|record| ApiClient {
+                id: record.id.clone(),
+                name: record.name.clone(),
+                scopes: record.scopes.clone(),
+            })
+    }
+
+    pub fn list(&self) -> Vec<ApiKeyRecord> {
+        self.refresh();
+        let snapshot = self.snapshot.read().unwrap();
+        self.static_keys.iter().chain(snapshot.keys.iter()).cloned().collect()
+    }
+
+    /// Creates a key and returns it together with its record. The plain key is only available here.
+    pub fn issue(&self, name: &str, scopes: Vec<ApiScope>) -> Result<(String, ApiKeyRecord), Box<dyn std::error::Error>> {
+        let key = format!("{KEY_PREFIX}{}", random_hex(32));
+        let record = ApiKeyRecord {
+            id: random_hex(8),
+            name: name.to_string(),
+            key_hash: hash_key(&key),
+            scopes,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            revoked: false,
+        };
+        self.update(|keys| keys.push(record.clone()))?;
+        Ok((key, record))
+    }
+
+    /// Returns `Ok(false)` when no runtime-issued key has this id.
+    pub fn revoke(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        self.update(|keys| match keys.iter_mut().find(|record| record.id == id) {
+            Some(record) => {
+                record.revoked = true;
+                true
+            },
+            None => false,
+        })
+    }
+
+    /// Applies `change` to the keys currently in the file and writes them back, holding the lock
+    /// file so no other process changes the file in between.
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<ApiKeyRecord>) -> T) -> Result<T, Box<dyn std::error::Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("json.lock"))?;
+        // released when `lock` is dropped
+        lock.lock()?;
+
+        let mut keys = read_keys(&self.path)?;
+        let result = change(&mut keys);
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&keys)?)?;
+        fs::rename(tmp, &self.path)?;
+
+        let stamp = file_stamp(&self.path)?;
+        *self.snapshot.write().unwrap() = Snapshot { stamp, keys };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("api-keys-{name}-{}", random_hex(4)));
+        dir.join("api_keys.json")
+    }
+
+    #[test]
+    fn parses_scopes_ignoring_case() {
+        assert_eq!("Read".parse::<ApiScope>(), Ok(ApiScope::Read));
+        assert_eq!("admin".parse::<ApiScope>(), Ok(ApiScope::Admin));
+        assert!("write".parse::<ApiScope>().is_err());
+    }
+
+    #[test]
+    fn authenticates_static_keys_by_hash() {
+        let config = ApiKeyConfig { name: "frontend".to_string(), key_hash: hash_key("wm_secret").to_uppercase(), scopes: vec![ApiScope::Read] };
+        let store = ApiKeyStore::load(&keys_file("static"), &[config]).unwrap();
+        let client = store.authenticate("wm_secret").unwrap();
+        assert_eq!(client.id, "config:frontend");
+        assert_eq!(client.scopes, vec![ApiScope::Read]);
+        assert!(store.authenticate("wm_other").is_none());
+    }
+
+    #[test]
+    fn sees_keys_revoked_by_another_process() {
+        let path = keys_file("revoke");
+        let server = ApiKeyStore::load(&path, &[]).unwrap();
+        let (key, record) = server.issue("payouts", vec![ApiScope::Build]).unwrap();
+        assert!(server.authenticate(&key).is_some());
+
+        let cli = ApiKeyStore::load(&path, &[]).unwrap();
+        assert!(cli.revoke(&record.id).unwrap());
+        assert!(server.authenticate(&key).is_none());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keeps_changes_made_by_another_process() {
+        let path = keys_file("merge");
+        let server = ApiKeyStore::load(&path, &[]).unwrap();
+        let (revoked, revoked_record) = server.issue("old", vec![ApiScope::Read]).unwrap();
+
+        let cli = ApiKeyStore::load(&path, &[]).unwrap();
+        assert!(cli.revoke(&revoked_record.id).unwrap());
+        let (issued, _) = cli.issue("cli", vec![ApiScope::Read]).unwrap();
+
+        // the server's own change must not bring back its stale view of the file
+        let (own, _) = server.issue("server", vec![ApiScope::Read]).unwrap();
+        assert!(server.authenticate(&revoked).is_none());
+        assert!(server.authenticate(&issued).is_some());
+        assert!(server.authenticate(&own).is_some());
+        assert_eq!(ApiKeyStore::load(&path, &[]).unwrap().list().len(), 3);
+        assert!(!server.revoke("missing").unwrap());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use crate::config::{Cli, Config, KeysCommand};
+use super::api_keys::ApiKeyStore;
+
+/// Runs `backend keys ...` against the keys file and exits without starting the server.
+pub fn run_keys_command(cli: &Cli, command: &KeysCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let (config, errors) = Config::load_unvalidated(cli);
+    if !errors.is_empty() {
+        return Err(errors.join("; ").into());
+    }
+    let store = ApiKeyStore::load(Path::new(&config.auth.keys_file), &config.auth.keys)?;
+
+    match command {
+        KeysCommand::Issue { name, scopes } => {
+            let (key, record) = store.issue(name, scopes.clone())?;
+            println!("id:     {}", record.id);
+            println!("scopes: {:?}", record.scopes);
+            println!("key:    {key}");
+            println!("The key is shown only once, store it now.");
+        },
+        KeysCommand::Revoke { id } => {
+            if store.revoke(id)? {
+                println!("Revoked {id}");
+            } else {
+                return Err(format!("No issued key with id {id}").into());
+            }
+        },
+        KeysCommand::List => {
+            for record in store.list() {
+                let state = if record.revoked { "revoked" } else { "active" };
+                println!("{}\t{}\t{:?}\t{state}", record.id, record.name, record.scopes);
+            }
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{header, StatusCode},
+    middleware::Next,
+    web, Error, HttpMessage, HttpResponse,
+};
+use ethers::types::Address;
+use futures::StreamExt;
+use serde::Deserialize;
+use tracing::{error, warn};
+
+use crate::config::config;
+use crate::contracts::token_list::TokenRegistry;
+use crate::routes::responses::ErrorResponse;
+use super::api_keys::{ApiKeyStore, ApiScope};
+use super::siwe::{SessionStore, SESSION_HEADER};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Scope needed for a request path, `None` for public endpoints.
+pub fn required_scope(path: &str) -> Option<ApiScope> {
+    match path {
+        "/token/balanceOf" | "/token/allowance" | "/token/list" => Some(ApiScope::Read),
+        "/token721/ownerOf" | "/token721/balanceOf" | "/token721/tokenURI" => Some(ApiScope::Read),
+        "/token1155/balanceOf" | "/token1155/balanceOfBatch" => Some(ApiScope::Read),
+        "/weth/balanceOf" | "/wallet/previewDisperseToken" => Some(ApiScope::Read),
+        "/web3/verifyMessage" | "/web3/verifyTypedData" | "/web3/gas" => Some(ApiScope::Read),
+        "/web3/sendSignedTransaction" => Some(ApiScope::Broadcast),
+        "/web3/signTransaction" | "/web3/signMessage" => Some(ApiScope::Sign),
+        "/openapi.json" => None,
+        _ if path == "/docs" || path.starts_with("/docs/") => None,
+        _ if path.starts_with("/auth") => None,
+        _ if path.starts_with("/health") || path.starts_with("/metrics") => None,
+        _ if path.starts_with("/admin") => Some(ApiScope::Admin),
+        _ => Some(ApiScope::Build),
+    }
+}
+
+fn extract_key(req: &ServiceRequest) -> Option<String> {
+    let headers = req.headers();
+    if let Some(key) = headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string())
+}
+
+fn authorize(req: &ServiceRequest) -> Result<(), (StatusCode, String)> {
+    let Some(scope) = required_scope(req.path()) else {
+        return Ok(());
+    };
+    let store = req.app_data::<web::Data<ApiKeyStore>>()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "API key store is not configured".to_string()))?;
+
+    let Some(key) = extract_key(req) else {
+        return Err((StatusCode::UNAUTHORIZED, "Missing API key".to_string()));
+    };
+    let Some(client) = store.authenticate(&key) else {
+        warn!(path = req.path(), "Rejected invalid API key");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid API key".to_string()));
+    };
+    if !client.scopes.contains(&scope) {
+        warn!(client = %client.name, path = req.path(), scope = ?scope, "API key lacks required scope");
+        return Err((StatusCode::FORBIDDEN, format!("API key lacks the {scope:?} scope")));
+    }
+
+    req.extensions_mut().insert(client);
+    Ok(())
+}
+
+pub async fn api_key_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if !config().auth.enabled {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    match authorize(&req) {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err((status, message)) => {
+            let res = HttpResponse::build(status).json(ErrorResponse::new(message));
+            Ok(req.into_response(res).map_into_right_body())
+        },
+    }
+}
+
+/// Largest body read when checking `sender` or `token`, the default `web::Json` limit.
+const MAX_CHECKED_BODY: usize = 2 * 1024 * 1024;
+
+/// Reads the body and hands it back to the request untouched for the handler.
+pub(crate) async fn read_body(req: &mut ServiceRequest) -> Result<web::Bytes, (StatusCode, String)> {
+    let mut payload = req.take_payload();
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_| (StatusCode::BAD_REQUEST, "Failed to read request body".to_string()))?;
+        if body.len() + chunk.len() > MAX_CHECKED_BODY {
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large".to_string()));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+    req.set_payload(Payload::from(body.clone()));
+    Ok(body)
+}
+
+#[derive(Deserialize)]
+struct SenderField {
+    sender: Option<Address>,
+}
+
+/// Routes that build transactions on behalf of a `sender`, `/token` and its per-standard variants.
+fn requires_session(path: &str) -> bool {
+    path.starts_with("/wallet/") || path.starts_with("/weth/") || path.starts_with("/token")
+}
+
+async fn authorize_session(req: &mut ServiceRequest) -> Result<(), (StatusCode, String)> {
+    let store = req.app_data::<web::Data<SessionStore>>()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Session store is not configured".to_string()))?
+        .clone();
+    let Some(token) = req.headers().get(SESSION_HEADER).and_then(|value| value.to_str().ok()) else {
+        return Err((StatusCode::UNAUTHORIZED, "Missing session token, sign in through /auth/login".to_string()));
+    };
+    let Some(session) = store.authenticate(token.trim()) else {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired session".to_string()));
+    };
+
+    let body = read_body(req).await?;
+    let sender = serde_json::from_slice::<SenderField>(&body).ok().and_then(|field| field.sender);
+
+    if let Some(sender) = sender {
+        if sender != session.address {
+            warn!(path = req.path(), sender = ?sender, address = ?session.address, "Rejected request for another sender");
+            return Err((StatusCode::FORBIDDEN, "sender does not match the signed-in address".to_string()));
+        }
+    }
+    req.extensions_mut().insert(session);
+    Ok(())
+}
+
+pub async fn session_auth(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if !config().siwe.enabled || !requires_session(req.path()) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    match authorize_session(&mut req).await {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err((status, message)) => {
+            let res = HttpResponse::build(status).json(ErrorResponse::new(message));
+            Ok(req.into_response(res).map_into_right_body())
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenField {
+    token: Option<Address>,
+}
+
+/// Routes taking an ERC20 `token`.
+fn takes_erc20_token(path: &str) -> bool {
+    path.starts_with("/wallet/") || path.starts_with("/token/")
+}
+
+async fn authorize_token(req: &mut ServiceRequest) -> Result<(), (StatusCode, String)> {
+    let registry = req.app_data::<web::Data<TokenRegistry>>()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Token registry is not configured".to_string()))?
+        .clone();
+    if !registry.is_strict() {
+        return Ok(());
+    }
+
+    let body = read_body(req).await?;
+    let Some(token) = serde_json::from_slice::<TokenField>(&body).ok().and_then(|field| field.token) else {
+        return Ok(());
+    };
+    match registry.check(token).await {
+        Ok(None) => Ok(()),
+        Ok(Some(reason)) => {
+            warn!(path = req.path(), token = ?token, reason = %reason, "Rejected token");
+            Err((StatusCode::BAD_REQUEST, reason))
+        },
+        Err(err) => {
+            error!(error = ?err, token = ?token, "Failed to verify token metadata");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to verify token metadata".to_string()))
+        },
+    }
+}
+
+/// Strict token lists: rejects ERC20 routes whose `token` is unlisted or has metadata disagreeing
+/// with the list.
+pub async fn token_allowlist(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if !takes_erc20_token(req.path()) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+    match authorize_token(&mut req).await {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err((status, message)) => {
+            let res = HttpResponse::build(status).json(ErrorResponse::new(message));
+            Ok(req.into_response(res).map_into_right_body())
+        },
+    }
+}
@@ -0,0 +1,5 @@
+
+pub mod api_keys;
+pub mod middleware;
+pub mod cli;
+pub mod siwe;
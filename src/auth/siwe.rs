@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::DateTime;
+use ethers::{
+    types::{Address, Signature},
+    utils::to_checksum,
+};
+
+use crate::config::SiweConfig;
+use crate::contracts::provider::EthProvider;
+use super::api_keys::{hash_key, random_hex};
+
+pub const SESSION_HEADER: &str = "x-session-token";
+
+const SESSION_PREFIX: &str = "siwe_";
+const PREAMBLE_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+fn parse_timestamp(value: &str) -> Result<u64, String> {
+    let timestamp = DateTime::parse_from_rfc3339(value).map_err(|_| format!("{value:?} is not an RFC 3339 timestamp"))?;
+    Ok(timestamp.timestamp().max(0) as u64)
+}
+
+/// An EIP-4361 message. Timestamps are unix seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: u64,
+    pub expiration_time: Option<u64>,
+    pub not_before: Option<u64>,
+}
+
+impl FromStr for SiweMessage {
+    type Err = String;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        let mut lines = message.lines();
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(PREAMBLE_SUFFIX))
+            .ok_or("missing the sign-in preamble")?
+            .to_string();
+        let address_line = lines.next().ok_or("missing address")?;
+        let address = Address::from_str(address_line).map_err(|_| format!("{address_line:?} is not an address"))?;
+        if to_checksum(&address, None) != address_line {
+            return Err("address must be EIP-55 checksummed".to_string());
+        }
+
+        let mut statement = None;
+        let mut fields = HashMap::new();
+        for line in lines {
+            if line.is_empty() || line == "Resources:" || line.starts_with("- ") {
+                continue;
+            }
+            match line.split_once(": ") {
+                Some((key, value)) if !fields.is_empty() || key == "URI" => {
+                    fields.insert(key, value);
+                },
+                _ if fields.is_empty() && statement.is_none() => statement = Some(line.to_string()),
+                _ => return Err(format!("unexpected line {line:?}")),
+            }
+        }
+        let field = |key: &str| fields.get(key).map(|value| value.to_string()).ok_or(format!("missing {key}"));
+
+        Ok(Self {
+            domain,
+            address,
+            statement,
+            uri: field("URI")?,
+            version: field("Version")?,
+            chain_id: field("Chain ID")?.parse().map_err(|_| "Chain ID is not a number".to_string())?,
+            nonce: field("Nonce")?,
+            issued_at: parse_timestamp(&field("Issued At")?)?,
+            expiration_time: fields.get("Expiration Time").map(|value| parse_timestamp(value)).transpose()?,
+            not_before: fields.get("Not Before").map(|value| parse_timestamp(value)).transpose()?,
+        })
+    }
+}
+
+/// A signed-in address, stored in the request extensions.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub address: Address,
+    pub expires_at: u64,
+}
+
+/// Outstanding nonces and active sessions, kept in memory. Sessions are keyed by the sha256 of
+/// their token, like API keys, and do not survive a restart.
+pub struct SessionStore {
+    config: SiweConfig,
+    chain_id: Option<u64>,
+    nonces: Mutex<HashMap<String, u64>>,
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    pub fn new(config: SiweConfig, chain_id: Option<u64>) -> Self {
+        Self {
+            config,
+            chain_id,
+            nonces: Mutex::new(HashMap::new()),
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// A single-use nonce for the next sign-in message.
+    pub fn issue_nonce(&self) -> String {
+        let now = now();
+        let nonce = random_hex(16);
+        let mut nonces = self.nonces.lock().unwrap();
+        nonces.retain(|_, expires_at| *expires_at > now);
+        nonces.insert(nonce.clone(), now + self.config.nonce_ttl_secs);
+        nonce
+    }
+
+    /// Verifies a signed EIP-4361 message and opens a session for its address. The error is
+    /// safe to return to the caller.
+    pub fn login(&self, message: &str, signature: &Signature) -> Result<(String, Session), String> {
+        let now = now();
+        let siwe = SiweMessage::from_str(message).map_err(|err| format!("Invalid sign-in message: {err}"))?;
+        if siwe.domain != self.config.domain {
+            return Err(format!("Sign-in message is for {:?}, expected {:?}", siwe.domain, self.config.domain));
+        }
+        if siwe.version != "1" {
+            return Err(format!("Unsupported sign-in message version {:?}", siwe.version));
+        }
+        if self.chain_id.is_some_and(|chain_id| chain_id != siwe.chain_id) {
+            return Err(format!("Sign-in message is for chain {}", siwe.chain_id));
+        }
+        if siwe.expiration_time.is_some_and(|expiration_time| expiration_time <= now) {
+            return Err("Sign-in message has expired".to_string());
+        }
+        if siwe.not_before.is_some_and(|not_before| not_before > now) {
+            return Err("Sign-in message is not valid yet".to_string());
+        }
+
+        // smart contract wallets (EIP-1271) are not supported, the signer must be the address itself
+        let signer = EthProvider::recover_message_signer(message.as_bytes(), signature).ok();
+        if signer != Some(siwe.address) {
+            return Err("Signature does not match the sign-in message address".to_string());
+        }
+        // the nonce is only spent by a valid signature, so a bad attempt can be retried
+        match self.nonces.lock().unwrap().remove(&siwe.nonce) {
+            Some(expires_at) if expires_at > now => {},
+            _ => return Err("Unknown or expired nonce".to_string()),
+        }
+
+        let expires_at = siwe.expiration_time.unwrap_or(u64::MAX).min(now + self.config.session_ttl_secs);
+        let session = Session { address: siwe.address, expires_at };
+        let token = format!("{SESSION_PREFIX}{}", random_hex(32));
+        let mut sessions = self.sessions.write().unwrap();
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions.insert(hash_key(&token), session.clone());
+        Ok((token, session))
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<Session> {
+        let sessions = self.sessions.read().unwrap();
+        sessions.get(&hash_key(token)).filter(|session| session.expires_at > now()).cloned()
+    }
+
+    /// Returns `false` when the token has no session.
+    pub fn logout(&self, token: &str) -> bool {
+        self.sessions.write().unwrap().remove(&hash_key(token)).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+
+    use super::*;
+
+    const KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn message(address: Address, nonce: &str, expiration_time: &str) -> String {
+        format!(
+            "app.example.com wants you to sign in with your Ethereum account:\n\
+             {}\n\
+             \n\
+             Sign in to the wallet manager\n\
+             \n\
+             URI: https://app.example.com/login\n\
+             Version: 1\n\
+             Chain ID: 1\n\
+             Nonce: {nonce}\n\
+             Issued At: 2026-01-01T00:00:00Z\n\
+             Expiration Time: {expiration_time}\n\
+             Resources:\n\
+             - https://app.example.com/terms",
+            to_checksum(&address, None),
+        )
+    }
+
+    fn store() -> SessionStore {
+        let config = SiweConfig { enabled: true, domain: "app.example.com".to_string(), ..SiweConfig::default() };
+        SessionStore::new(config, Some(1))
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let address = Address::repeat_byte(0x11);
+        let siwe = SiweMessage::from_str(&message(address, "abc123", "2100-01-01T00:00:00Z")).unwrap();
+        assert_eq!(siwe, SiweMessage {
+            domain: "app.example.com".to_string(),
+            address,
+            statement: Some("Sign in to the wallet manager".to_string()),
+            uri: "https://app.example.com/login".to_string(),
+            version: "1".to_string(),
+            chain_id: 1,
+            nonce: "abc123".to_string(),
+            issued_at: 1_767_225_600,
+            expiration_time: Some(4_102_444_800),
+            not_before: None,
+        });
+    }
+
+    #[test]
+    fn statement_is_optional() {
+        let text = message(Address::repeat_byte(0x11), "abc123", "2100-01-01T00:00:00Z")
+            .replace("Sign in to the wallet manager\n\n", "");
+        assert_eq!(SiweMessage::from_str(&text).unwrap().statement, None);
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let address = Address::repeat_byte(0xab);
+        let text = message(address, "abc123", "2100-01-01T00:00:00Z");
+        let lowercase = text.replace(&to_checksum(&address, None), &format!("{address:?}"));
+        assert_eq!(SiweMessage::from_str(&lowercase).unwrap_err(), "address must be EIP-55 checksummed");
+        assert_eq!(SiweMessage::from_str("hello").unwrap_err(), "missing the sign-in preamble");
+        assert_eq!(SiweMessage::from_str(&text.replace("Nonce: abc123\n", "")).unwrap_err(), "missing Nonce");
+        assert_eq!(
+            SiweMessage::from_str(&text.replace("2100-01-01T00:00:00Z", "tomorrow")).unwrap_err(),
+            "\"tomorrow\" is not an RFC 3339 timestamp",
+        );
+        assert_eq!(
+            SiweMessage::from_str(&text.replace("Version: 1\n", "Version: 1\nsurprise\n")).unwrap_err(),
+            "unexpected line \"surprise\"",
+        );
+    }
+
+    #[tokio::test]
+    async fn logs_in_once_per_nonce() {
+        let wallet: LocalWallet = KEY.parse().unwrap();
+        let store = store();
+        let nonce = store.issue_nonce();
+        let text = message(wallet.address(), &nonce, "2100-01-01T00:00:00Z");
+        let signature = wallet.sign_message(&text).await.unwrap();
+
+        let (token, session) = store.login(&text, &signature).unwrap();
+        assert_eq!(session.address, wallet.address());
+        assert_eq!(store.authenticate(&token).unwrap().address, wallet.address());
+        assert_eq!(store.login(&text, &signature).unwrap_err(), "Unknown or expired nonce");
+
+        assert!(store.logout(&token));
+        assert!(store.authenticate(&token).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_expired_and_forged_messages() {
+        let wallet: LocalWallet = KEY.parse().unwrap();
+        let store = store();
+        let nonce = store.issue_nonce();
+
+        let expired = message(wallet.address(), &nonce, "2020-01-01T00:00:00Z");
+        let signature = wallet.sign_message(&expired).await.unwrap();
+        assert_eq!(store.login(&expired, &signature).unwrap_err(), "Sign-in message has expired");
+
+        let forged = message(Address::repeat_byte(0x11), &nonce, "2100-01-01T00:00:00Z");
+        let signature = wallet.sign_message(&forged).await.unwrap();
+        assert_eq!(store.login(&forged, &signature).unwrap_err(), "Signature does not match the sign-in message address");
+
+        // neither attempt spent the nonce
+        let valid = message(wallet.address(), &nonce, "2100-01-01T00:00:00Z");
+        let signature = wallet.sign_message(&valid).await.unwrap();
+        assert!(store.login(&valid, &signature).is_ok());
+    }
+}
@@ -1,24 +1,41 @@
-use std::fs::read_to_string;
-
 use ethers::{
-    abi::Abi,
     contract::Contract,
-    providers::{Http, Provider}, 
-    types::{Address, TransactionRequest, U256},
+    types::{Address, TransactionRequest, H256, U256},
+    utils::keccak256,
 };
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
 
-use super::provider::EthProvider;
+use super::load_abi;
+use super::provider::{EthProvider, RpcProvider};
 
 pub struct DisperseCollect {
     pub provider: EthProvider,
-    pub contract: Contract<Provider<Http>>,
+    pub contract: Contract<RpcProvider>,
+}
+
+/// Outcome of checking the configured address against the expected DisperseCollect deployment.
+#[derive(Serialize, Clone, Debug)]
+pub struct DeploymentReport {
+    pub address: Address,
+    pub code_hash: Option<H256>,
+    pub owner: Option<Address>,
+    pub problems: Vec<String>,
+}
+
+impl DeploymentReport {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
 }
 
 impl DisperseCollect {
     pub fn new(rpc_url: &str, contract_address: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let provider = EthProvider::new(rpc_url)?;
         let address = contract_address.parse::<Address>()?;
-        let abi = Abi::load(read_to_string("./config/abi/disperse_collect.json")?.as_bytes())?;
+        let abi = load_abi("disperse_collect")?;
         let contract = Contract::new(address, abi, provider.provider.clone());
         Ok(Self {
             provider,
@@ -82,4 +99,75 @@ impl DisperseCollect {
             .from(sender);
         Ok(tx)
     }
+
+    pub async fn query_owner(&self) -> Result<Address, Box<dyn std::error::Error>> {
+        let owner = self.contract.method::<_, Address>("owner", ())?.call().await?;
+        Ok(owner)
+    }
+
+    pub async fn verify_deployment(&self, known_code_hashes: &[H256]) -> DeploymentReport {
+        let address = self.contract.address();
+        let mut report = DeploymentReport {
+            address,
+            code_hash: None,
+            owner: None,
+            problems: vec![],
+        };
+
+        match self.provider.get_code(address).await {
+            Ok(code) if code.is_empty() => {
+                report.problems.push(format!("No contract deployed at {address:?}, check DISPERSE_COLLECT_CONTRACT_ADDRESS"));
+                return report;
+            },
+            Ok(code) => {
+                let code_hash = H256::from(keccak256(&code));
+                if !known_code_hashes.is_empty() && !known_code_hashes.contains(&code_hash) {
+                    report.problems.push(format!("Bytecode hash {code_hash:?} at {address:?} is not a known DisperseCollect build"));
+                }
+                report.code_hash = Some(code_hash);
+            },
+            Err(err) => {
+                warn!(error = %err, address = ?address, "Failed to fetch DisperseCollect bytecode");
+                report.problems.push(format!("Failed to fetch bytecode at {address:?}"));
+                return report;
+            },
+        }
+
+        match self.query_owner().await {
+            Ok(owner) => report.owner = Some(owner),
+            Err(err) => {
+                warn!(error = %err, address = ?address, "DisperseCollect owner() call failed");
+                report.problems.push("owner() call failed, the contract may not match the DisperseCollect ABI".to_string());
+            },
+        }
+
+        report
+    }
+}
+
+/// The latest [`DeploymentReport`], verified again once it is older than `ttl` so a provider
+/// outage at startup doesn't outlive the outage.
+pub struct DeploymentStatus {
+    known_code_hashes: Vec<H256>,
+    ttl: Duration,
+    latest: Mutex<(Instant, DeploymentReport)>,
+}
+
+impl DeploymentStatus {
+    pub fn new(report: DeploymentReport, known_code_hashes: Vec<H256>, ttl: Duration) -> Self {
+        Self {
+            known_code_hashes,
+            ttl,
+            latest: Mutex::new((Instant::now(), report)),
+        }
+    }
+
+    pub async fn current(&self, disperse_collect: &DisperseCollect) -> DeploymentReport {
+        // held across the check so concurrent probes wait for one verification instead of each running it
+        let mut latest = self.latest.lock().await;
+        if latest.0.elapsed() >= self.ttl {
+            *latest = (Instant::now(), disperse_collect.verify_deployment(&self.known_code_hashes).await);
+        }
+        latest.1.clone()
+    }
 }
@@ -0,0 +1,66 @@
+use ethers::{
+    contract::Contract,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+};
+
+use super::load_abi;
+use super::provider::{EthProvider, RpcProvider};
+
+pub struct ERC1155 {
+    pub provider: EthProvider,
+    pub contract: Contract<RpcProvider>,
+}
+
+impl ERC1155 {
+    pub fn new(rpc_url: &str, contract_address: Address) -> Result<Self, Box<dyn std::error::Error>> {
+        let provider = EthProvider::new(rpc_url)?;
+        let abi = load_abi("erc1155")?;
+        let contract = Contract::new(contract_address, abi, provider.provider.clone());
+        Ok(Self {
+            provider,
+            contract,
+        })
+    }
+
+    pub fn create_safe_transfer_from_tx(&self, sender: Address, from: Address, to: Address, id: U256, amount: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("safeTransferFrom", (from, to, id, amount, Bytes::new()))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_safe_batch_transfer_from_tx(&self, sender: Address, from: Address, to: Address, ids: Vec<U256>, amounts: Vec<U256>) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("safeBatchTransferFrom", (from, to, ids, amounts, Bytes::new()))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_set_approval_for_all_tx(&self, sender: Address, operator: Address, approved: bool) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("setApprovalForAll", (operator, approved))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_balance_of_batch_tx(&self, accounts: Vec<Address>, ids: Vec<U256>) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("balanceOfBatch", (accounts, ids))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data);
+        Ok(tx)
+    }
+
+    /// `balances[i]` is the balance of `accounts[i]` in token `ids[i]`.
+    pub async fn query_balance_of_batch(&self, accounts: Vec<Address>, ids: Vec<U256>) -> Result<Vec<U256>, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_balance_of_batch_tx(accounts, ids)?;
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "balanceOfBatch", result).await
+    }
+}
@@ -1,25 +1,28 @@
-use std::fs::read_to_string;
-
 use ethers::{
-    abi::Abi,
     contract::Contract,
-    providers::{Http, Provider},
-    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, U256},
+    providers::call_raw::spoof,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::{EIP712Domain, TypedData}},
+        Address, Signature, TransactionRequest, H256, U256,
+    },
 };
+use serde_json::json;
 
 use crate::utils::web3::{parse_u256, u256_to_string};
 
-use super::provider::EthProvider;
+use super::load_abi;
+use super::provider::{CallOutputError, EthProvider, RpcProvider};
+use super::storage::{find_mapping_slot, MappingSlot};
 
 pub struct ERC20 {
     pub provider: EthProvider,
-    pub contract: Contract<Provider<Http>>,
+    pub contract: Contract<RpcProvider>,
 }
 
 impl ERC20 {
     pub fn new(rpc_url: &str, contract_address: Address) -> Result<Self, Box<dyn std::error::Error>> {
         let provider = EthProvider::new(rpc_url)?;
-        let abi = Abi::load(read_to_string("./config/abi/erc20.json")?.as_bytes())?;
+        let abi = load_abi("erc20")?;
         let contract = Contract::new(contract_address, abi, provider.provider.clone());
         Ok(Self {
             provider,
@@ -45,6 +48,24 @@ impl ERC20 {
         Ok(tx)
     }
 
+    pub fn create_increase_allowance_tx(&self, sender: Address, spender: Address, added_value: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("increaseAllowance", (spender, added_value))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_decrease_allowance_tx(&self, sender: Address, spender: Address, subtracted_value: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("decreaseAllowance", (spender, subtracted_value))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
     pub fn create_transfer_from_tx(&self, sender: Address, from: Address, to: Address, amount: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
         let data = self.contract.encode("transferFrom", (from, to, amount))?;
         let tx = TransactionRequest::new()
@@ -54,6 +75,17 @@ impl ERC20 {
         Ok(tx)
     }
 
+    pub fn create_permit_tx(&self, sender: Address, owner: Address, spender: Address, value: U256, deadline: U256, signature: Signature) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let v = permit_v(signature.v).ok_or_else(|| format!("Invalid signature v of {}", signature.v))?;
+        let (r, s) = (H256::from(<[u8; 32]>::from(signature.r)), H256::from(<[u8; 32]>::from(signature.s)));
+        let data = self.contract.encode("permit", (owner, spender, value, deadline, v, r, s))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
     pub fn create_balance_of_tx(&self, owner: Address) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
         let data = self.contract.encode("balanceOf", (owner,))?;
         let tx = TransactionRequest::new()
@@ -89,25 +121,204 @@ impl ERC20 {
     pub async fn query_balance_of(&self, owner: Address) -> Result<U256, Box<dyn std::error::Error>> {
         let tx: TransactionRequest = self.create_balance_of_tx(owner)?;
         let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
-        Ok(U256::from_big_endian(&result))
+        self.provider.decode_output(&self.contract, "balanceOf", result).await
     }
 
     pub async fn query_allowance(&self, owner: Address, spender: Address) -> Result<U256, Box<dyn std::error::Error>> {
         let tx: TransactionRequest = self.create_allowance_tx(owner, spender)?;
         let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
-        Ok(U256::from_big_endian(&result))
+        self.provider.decode_output(&self.contract, "allowance", result).await
     }
 
     pub async fn query_total_supply(&self) -> Result<U256, Box<dyn std::error::Error>> {
         let tx: TransactionRequest = self.create_total_supply_tx()?;
         let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
-        Ok(U256::from_big_endian(&result))
+        self.provider.decode_output(&self.contract, "totalSupply", result).await
     }
 
     pub async fn query_decimals(&self) -> Result<U256, Box<dyn std::error::Error>> {
         let tx: TransactionRequest = self.create_decimals_tx()?;
         let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
-        Ok(U256::from_big_endian(&result))
+        self.provider.decode_output(&self.contract, "decimals", result).await
+    }
+
+    /// Simulates `tx`, a call to `function` returning `bool` such as `transfer` or `approve`.
+    /// Tokens like USDT return nothing instead of a bool, which counts as success; an explicit
+    /// `false` does not. Reverts are returned as errors.
+    pub async fn simulate_bool_call(&self, function: &str, tx: TransactionRequest) -> Result<bool, Box<dyn std::error::Error>> {
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        if result.is_empty() {
+            return Ok(true);
+        }
+        self.provider.decode_output(&self.contract, function, result).await
+    }
+
+    /// Whether `approve(spender, amount)` from `owner` needs an `approve(spender, 0)` first, as
+    /// USDT-style tokens require to change a non-zero allowance to another non-zero value: the
+    /// approve fails against the current state, by reverting or returning false, but succeeds from a
+    /// zero allowance. An approve failing either way, e.g. on a paused token, needs no reset.
+    pub async fn needs_allowance_reset(&self, owner: Address, spender: Address, amount: U256) -> Result<bool, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_approve_tx(owner, spender, amount)?;
+        if approves(self.simulate_bool_call("approve", tx.clone()).await)? {
+            return Ok(false);
+        }
+
+        // without the allowance mapping, a spender without an allowance stands in for a reset one
+        let (tx, state) = match self.allowance_override(spender, &[(owner, U256::zero())]).await? {
+            Some(state) => (tx, state),
+            None => (self.create_approve_tx(owner, Address::random(), amount)?, spoof::state()),
+        };
+        let approved = match self.provider.query_transaction_with_state(&TypedTransaction::Legacy(tx), &state).await {
+            Ok(result) if result.is_empty() => Ok(true),
+            Ok(result) => self.provider.decode_output(&self.contract, "approve", result).await,
+            Err(err) => Err(err),
+        };
+        approves(approved)
+    }
+
+    pub async fn query_nonces(&self, owner: Address) -> Result<U256, Box<dyn std::error::Error>> {
+        let tx = TransactionRequest::new().to(self.contract.address()).data(self.contract.encode("nonces", (owner,))?);
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "nonces", result).await
+    }
+
+    pub async fn query_domain_separator(&self) -> Result<H256, Box<dyn std::error::Error>> {
+        let tx = TransactionRequest::new().to(self.contract.address()).data(self.contract.encode("DOMAIN_SEPARATOR", ())?);
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "DOMAIN_SEPARATOR", result).await
+    }
+
+    pub async fn query_name(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let tx = TransactionRequest::new().to(self.contract.address()).data(self.contract.encode("name", ())?);
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "name", result).await
+    }
+
+    pub async fn query_symbol(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let tx = TransactionRequest::new().to(self.contract.address()).data(self.contract.encode("symbol", ())?);
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "symbol", result).await
+    }
+
+    pub async fn query_version(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let tx = TransactionRequest::new().to(self.contract.address()).data(self.contract.encode("version", ())?);
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "version", result).await
+    }
+
+    /// The EIP-712 domain the token verifies permits against, `None` if it does not implement
+    /// EIP-2612. Tokens without `version()` are matched against the common versions "1" and "2"
+    /// by comparing with `DOMAIN_SEPARATOR()`.
+    pub async fn permit_domain(&self) -> Result<Option<EIP712Domain>, Box<dyn std::error::Error>> {
+        let separator = match tokio::try_join!(self.query_domain_separator(), self.query_nonces(Address::zero())) {
+            Ok((separator, _)) => separator,
+            Err(err) if EthProvider::rpc_error_message(err.as_ref()).is_some() => return Ok(None),
+            // a contract without the function may return nothing instead of reverting
+            Err(err) if err.is::<CallOutputError>() => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let (name, chain_id) = match tokio::try_join!(self.query_name(), self.provider.get_chain_id()) {
+            Ok(result) => result,
+            // without a readable name the domain can't be rebuilt
+            Err(err) if err.is::<CallOutputError>() => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let mut versions = vec!["1".to_string(), "2".to_string()];
+        if let Ok(version) = self.query_version().await {
+            versions.insert(0, version);
+        }
+        Ok(versions
+            .into_iter()
+            .map(|version| EIP712Domain {
+                name: Some(name.clone()),
+                version: Some(version),
+                chain_id: Some(chain_id),
+                verifying_contract: Some(self.contract.address()),
+                salt: None,
+            })
+            .find(|domain| H256::from(domain.separator()) == separator))
+    }
+
+    /// EIP-712 `Permit` message in the `eth_signTypedData_v4` format.
+    pub fn permit_typed_data(domain: &EIP712Domain, owner: Address, spender: Address, value: U256, nonce: U256, deadline: U256) -> Result<TypedData, Box<dyn std::error::Error>> {
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [
+                    {"name": "name", "type": "string"},
+                    {"name": "version", "type": "string"},
+                    {"name": "chainId", "type": "uint256"},
+                    {"name": "verifyingContract", "type": "address"},
+                ],
+                "Permit": [
+                    {"name": "owner", "type": "address"},
+                    {"name": "spender", "type": "address"},
+                    {"name": "value", "type": "uint256"},
+                    {"name": "nonce", "type": "uint256"},
+                    {"name": "deadline", "type": "uint256"},
+                ],
+            },
+            "primaryType": "Permit",
+            "domain": domain,
+            "message": {
+                "owner": owner,
+                "spender": spender,
+                "value": value.to_string(),
+                "nonce": nonce.to_string(),
+                "deadline": deadline.to_string(),
+            },
+        });
+        Ok(serde_json::from_value(typed_data)?)
+    }
+
+    /// State override granting each `(owner, value)` an allowance of `value` for `spender`, for
+    /// simulating a transaction that runs after pending permits or approvals. `None` when the
+    /// token's allowance mapping can't be located.
+    pub async fn allowance_override(&self, spender: Address, allowances: &[(Address, U256)]) -> Result<Option<spoof::State>, Box<dyn std::error::Error>> {
+        let Some((probe_owner, _)) = allowances.first() else {
+            return Ok(Some(spoof::state()));
+        };
+        let allowance_tx = TypedTransaction::Legacy(self.create_allowance_tx(*probe_owner, spender)?);
+        let slot = find_mapping_slot(self.contract.address(), &[*probe_owner, spender], |state| {
+            let tx = allowance_tx.clone();
+            async move {
+                let result = self.provider.query_transaction_with_state(&tx, &state).await?;
+                Ok(U256::from_big_endian(&result))
+            }
+        }).await?;
+
+        Ok(slot.map(|slot: MappingSlot| {
+            let mut state = spoof::state();
+            for (owner, value) in allowances {
+                slot.override_value(&mut state, self.contract.address(), &[*owner, spender], *value);
+            }
+            state
+        }))
+    }
+
+    /// State override setting the balance of each `(owner, value)` to `value`, for simulating a
+    /// transaction that runs after pending deposits or transfers. `None` when the token's balance
+    /// mapping can't be located.
+    pub async fn balance_override(&self, balances: &[(Address, U256)]) -> Result<Option<spoof::State>, Box<dyn std::error::Error>> {
+        let Some((probe_owner, _)) = balances.first() else {
+            return Ok(Some(spoof::state()));
+        };
+        let balance_tx = TypedTransaction::Legacy(self.create_balance_of_tx(*probe_owner)?);
+        let slot = find_mapping_slot(self.contract.address(), &[*probe_owner], |state| {
+            let tx = balance_tx.clone();
+            async move {
+                let result = self.provider.query_transaction_with_state(&tx, &state).await?;
+                Ok(U256::from_big_endian(&result))
+            }
+        }).await?;
+
+        Ok(slot.map(|slot: MappingSlot| {
+            let mut state = spoof::state();
+            for (owner, value) in balances {
+                slot.override_value(&mut state, self.contract.address(), &[*owner], *value);
+            }
+            state
+        }))
     }
 
     pub async fn token_to_string(&self, amount: U256) -> Result<String, Box<dyn std::error::Error>> {
@@ -120,3 +331,40 @@ impl ERC20 {
         parse_u256(amount, decimals)
     }
 }
+
+/// The outcome of a simulated `approve`, a revert counting as `false`.
+fn approves(result: Result<bool, Box<dyn std::error::Error>>) -> Result<bool, Box<dyn std::error::Error>> {
+    match result {
+        Ok(approved) => Ok(approved),
+        Err(err) if EthProvider::rpc_error_message(err.as_ref()).is_some() => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// The `v` that `permit` passes to `ecrecover`, 27 or 28. Wallets also report it as 0 or 1, or
+/// EIP-155 style as `chain_id * 2 + 35` or `+ 36`; any other value is `None`.
+pub fn permit_v(v: u64) -> Option<u8> {
+    match v {
+        0 | 1 => Some(v as u8 + 27),
+        27 | 28 => Some(v as u8),
+        35.. => Some(((v - 35) % 2) as u8 + 27),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permit_v_is_normalized() {
+        assert_eq!(permit_v(0), Some(27));
+        assert_eq!(permit_v(1), Some(28));
+        assert_eq!(permit_v(27), Some(27));
+        assert_eq!(permit_v(28), Some(28));
+        // mainnet and a chain id of 137
+        assert_eq!((permit_v(37), permit_v(38)), (Some(27), Some(28)));
+        assert_eq!((permit_v(309), permit_v(310)), (Some(27), Some(28)));
+        assert_eq!((permit_v(2), permit_v(29), permit_v(34)), (None, None, None));
+    }
+}
@@ -0,0 +1,103 @@
+use ethers::{
+    abi::Tokenize,
+    contract::Contract,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+};
+
+use super::load_abi;
+use super::provider::{EthProvider, RpcProvider};
+
+pub struct ERC721 {
+    pub provider: EthProvider,
+    pub contract: Contract<RpcProvider>,
+}
+
+impl ERC721 {
+    pub fn new(rpc_url: &str, contract_address: Address) -> Result<Self, Box<dyn std::error::Error>> {
+        let provider = EthProvider::new(rpc_url)?;
+        let abi = load_abi("erc721")?;
+        let contract = Contract::new(contract_address, abi, provider.provider.clone());
+        Ok(Self {
+            provider,
+            contract,
+        })
+    }
+
+    pub fn create_transfer_from_tx(&self, sender: Address, from: Address, to: Address, token_id: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("transferFrom", (from, to, token_id))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    /// `safeTransferFrom(from, to, tokenId)`, which reverts when `to` is a contract that does not
+    /// accept ERC721 tokens.
+    pub fn create_safe_transfer_from_tx(&self, sender: Address, from: Address, to: Address, token_id: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        // overloaded with a variant taking `bytes data`, so `Contract::encode` can't pick it by name
+        let function = self.contract
+            .abi()
+            .functions_by_name("safeTransferFrom")?
+            .iter()
+            .find(|function| function.inputs.len() == 3)
+            .ok_or("safeTransferFrom(address,address,uint256) is missing from the ERC721 ABI")?;
+        let data = Bytes::from(function.encode_input(&(from, to, token_id).into_tokens())?);
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_set_approval_for_all_tx(&self, sender: Address, operator: Address, approved: bool) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("setApprovalForAll", (operator, approved))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_owner_of_tx(&self, token_id: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("ownerOf", (token_id,))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data);
+        Ok(tx)
+    }
+
+    pub fn create_balance_of_tx(&self, owner: Address) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("balanceOf", (owner,))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data);
+        Ok(tx)
+    }
+
+    pub fn create_token_uri_tx(&self, token_id: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("tokenURI", (token_id,))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data);
+        Ok(tx)
+    }
+
+    pub async fn query_owner_of(&self, token_id: U256) -> Result<Address, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_owner_of_tx(token_id)?;
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "ownerOf", result).await
+    }
+
+    pub async fn query_balance_of(&self, owner: Address) -> Result<U256, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_balance_of_tx(owner)?;
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "balanceOf", result).await
+    }
+
+    pub async fn query_token_uri(&self, token_id: U256) -> Result<String, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_token_uri_tx(token_id)?;
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "tokenURI", result).await
+    }
+}
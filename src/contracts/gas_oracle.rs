@@ -0,0 +1,320 @@
+use std::fmt;
+
+use ethers::types::{
+    transaction::eip2718::TypedTransaction, Eip1559TransactionRequest, TransactionRequest, U256,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::config::config;
+use crate::utils::web3::{gwei_to_string, parse_gwei};
+use super::provider::EthProvider;
+
+/// How quickly a transaction should be included, one of the priority fee percentiles in `gas.percentiles`.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Speed {
+    Slow,
+    #[default]
+    Standard,
+    Fast,
+}
+
+/// Fee options accepted by every transaction-building request, resolved by [`GasSuggestion::fees`].
+/// Without any, the standard tier of `/web3/gas` is used. Networks with a base fee get EIP-1559
+/// transactions paying the base fee plus the tip, up to `max_fee_per_gas`; elsewhere the
+/// transactions are legacy and the tier's gas price is capped at `max_fee_per_gas`.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Debug, Default)]
+pub struct FeeOptions {
+    /// Take the tip from this tier of `/web3/gas`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<Speed>,
+    /// Most paid per gas, in gwei
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!("40"))]
+    pub max_fee_per_gas: Option<String>,
+    /// Tip over the base fee in gwei, instead of the tier's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!("1.5"))]
+    pub max_priority_fee_per_gas: Option<String>,
+}
+
+/// Fee options that can't be met, a client error rather than a provider failure.
+#[derive(Debug)]
+pub struct InvalidFeeOptions(pub String);
+
+impl fmt::Display for InvalidFeeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidFeeOptions {}
+
+/// Suggested fees of one speed tier, in wei.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasTier {
+    /// Twice the next base fee plus the tip, so the transaction survives several full blocks
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl GasTier {
+    fn new(base_fee: U256, tip: U256) -> Self {
+        Self {
+            max_fee_per_gas: base_fee * 2 + tip,
+            max_priority_fee_per_gas: tip,
+        }
+    }
+
+    /// Both fees equal to `gas_price`, for networks without a base fee.
+    fn legacy(gas_price: U256) -> Self {
+        Self {
+            max_fee_per_gas: gas_price,
+            max_priority_fee_per_gas: gas_price,
+        }
+    }
+}
+
+/// Fees a transaction is signed with, in wei.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxFees {
+    Legacy { gas_price: U256 },
+    Eip1559 { max_fee_per_gas: U256, max_priority_fee_per_gas: U256 },
+}
+
+impl TxFees {
+    /// Most the transaction can pay per gas.
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match *self {
+            TxFees::Legacy { gas_price } => gas_price,
+            TxFees::Eip1559 { max_fee_per_gas, .. } => max_fee_per_gas,
+        }
+    }
+
+    /// `tx` as a transaction of this type paying these fees, in place of any gas price set on it.
+    pub fn apply(&self, tx: TransactionRequest) -> TypedTransaction {
+        match *self {
+            TxFees::Legacy { gas_price } => TypedTransaction::Legacy(tx.gas_price(gas_price)),
+            TxFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                let tx: Eip1559TransactionRequest = TypedTransaction::Legacy(tx).into();
+                TypedTransaction::Eip1559(tx.max_fee_per_gas(max_fee_per_gas).max_priority_fee_per_gas(max_priority_fee_per_gas))
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GasSuggestion {
+    /// Base fee of the next block, `None` on networks without EIP-1559
+    pub base_fee: Option<U256>,
+    pub slow: GasTier,
+    pub standard: GasTier,
+    pub fast: GasTier,
+}
+
+impl GasSuggestion {
+    pub fn tier(&self, speed: Speed) -> &GasTier {
+        match speed {
+            Speed::Slow => &self.slow,
+            Speed::Standard => &self.standard,
+            Speed::Fast => &self.fast,
+        }
+    }
+
+    /// The fees `options` resolve to. The tip is `max_priority_fee_per_gas` or the tier's, and
+    /// `max_fee_per_gas` defaults to twice the next base fee plus that tip. Fails when a fee
+    /// doesn't parse, the tip exceeds the cap or the cap is below the next base fee.
+    pub fn fees(&self, options: &FeeOptions) -> Result<TxFees, InvalidFeeOptions> {
+        let max_fee = parse_fee("max_fee_per_gas", options.max_fee_per_gas.as_deref())?;
+        let tip = parse_fee("max_priority_fee_per_gas", options.max_priority_fee_per_gas.as_deref())?;
+        if let (Some(max_fee), Some(tip)) = (max_fee, tip) {
+            if tip > max_fee {
+                return Err(InvalidFeeOptions("max_priority_fee_per_gas exceeds max_fee_per_gas".to_string()));
+            }
+        }
+
+        let tier = self.tier(options.speed.unwrap_or_default());
+        // without a base fee the whole price goes to the block producer, there is no separate tip
+        let Some(base_fee) = self.base_fee else {
+            let gas_price = tier.max_fee_per_gas;
+            return Ok(TxFees::Legacy { gas_price: max_fee.map_or(gas_price, |max_fee| max_fee.min(gas_price)) });
+        };
+        if let Some(max_fee) = max_fee.filter(|max_fee| *max_fee < base_fee) {
+            return Err(InvalidFeeOptions(format!(
+                "max_fee_per_gas of {} gwei is below the next base fee of {} gwei",
+                gwei_to_string(max_fee),
+                gwei_to_string(base_fee),
+            )));
+        }
+        let tip = tip.unwrap_or(tier.max_priority_fee_per_gas);
+        let max_fee = max_fee.unwrap_or(base_fee * 2 + tip);
+        // a tier's tip can still exceed a cap given on its own
+        Ok(TxFees::Eip1559 { max_fee_per_gas: max_fee, max_priority_fee_per_gas: tip.min(max_fee) })
+    }
+}
+
+/// Fee suggestions from the priority fees paid in recent blocks.
+pub struct GasOracle {
+    pub provider: EthProvider,
+}
+
+impl GasOracle {
+    pub fn new(rpc_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let provider = EthProvider::new(rpc_url)?;
+        Ok(Self { provider })
+    }
+
+    /// Slow, standard and fast tiers whose tips are the median over the last
+    /// `gas.fee_history_blocks` blocks of each percentile in `gas.percentiles`. Networks rejecting
+    /// `eth_feeHistory` or without a base fee get `eth_gasPrice` in every tier.
+    pub async fn suggest(&self) -> Result<GasSuggestion, Box<dyn std::error::Error>> {
+        let gas = &config().gas;
+        let history = match self.provider.get_fee_history(gas.fee_history_blocks, &gas.percentiles).await {
+            Ok(history) => Some(history),
+            Err(err) => match EthProvider::rpc_error_message(err.as_ref()) {
+                Some(reason) => {
+                    warn!(reason = %reason, "eth_feeHistory is not supported, using eth_gasPrice");
+                    None
+                },
+                None => return Err(err),
+            },
+        };
+        let base_fee = history.as_ref()
+            .and_then(|history| history.base_fee_per_gas.last().copied())
+            .filter(|base_fee| !base_fee.is_zero());
+        let (Some(history), Some(base_fee)) = (history, base_fee) else {
+            let tier = GasTier::legacy(self.provider.get_gas_price().await?);
+            return Ok(GasSuggestion { base_fee: None, slow: tier, standard: tier, fast: tier });
+        };
+
+        let tip = |index: usize| {
+            let mut tips: Vec<U256> = history.reward.iter().filter_map(|rewards| rewards.get(index).copied()).collect();
+            tips.sort();
+            tips.get(tips.len() / 2).copied().unwrap_or_default()
+        };
+        Ok(GasSuggestion {
+            base_fee: Some(base_fee),
+            slow: GasTier::new(base_fee, tip(0)),
+            standard: GasTier::new(base_fee, tip(1)),
+            fast: GasTier::new(base_fee, tip(2)),
+        })
+    }
+
+    /// The fees `options` resolve to, see [`GasSuggestion::fees`].
+    pub async fn fees(&self, options: &FeeOptions) -> Result<TxFees, Box<dyn std::error::Error>> {
+        let suggestion = self.suggest().await?;
+        Ok(suggestion.fees(options)?)
+    }
+}
+
+fn parse_fee(name: &str, value: Option<&str>) -> Result<Option<U256>, InvalidFeeOptions> {
+    value
+        .map(|value| parse_gwei(value).map_err(|_| InvalidFeeOptions(format!("{name}: {value:?} is not an amount in gwei"))))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::Address;
+
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    fn gwei(amount: u64) -> U256 {
+        U256::from(amount * GWEI)
+    }
+
+    fn options(speed: Option<Speed>, max_fee: Option<&str>, tip: Option<&str>) -> FeeOptions {
+        FeeOptions {
+            speed,
+            max_fee_per_gas: max_fee.map(str::to_string),
+            max_priority_fee_per_gas: tip.map(str::to_string),
+        }
+    }
+
+    /// Base fee of 10 gwei and tips of 1, 2 and 3 gwei.
+    fn eip1559() -> GasSuggestion {
+        let base_fee = gwei(10);
+        GasSuggestion {
+            base_fee: Some(base_fee),
+            slow: GasTier::new(base_fee, gwei(1)),
+            standard: GasTier::new(base_fee, gwei(2)),
+            fast: GasTier::new(base_fee, gwei(3)),
+        }
+    }
+
+    fn legacy() -> GasSuggestion {
+        let tier = GasTier::legacy(gwei(20));
+        GasSuggestion { base_fee: None, slow: tier, standard: tier, fast: tier }
+    }
+
+    #[test]
+    fn tiers_leave_room_for_the_base_fee_to_double() {
+        assert_eq!(GasTier::new(gwei(10), gwei(2)), GasTier { max_fee_per_gas: gwei(22), max_priority_fee_per_gas: gwei(2) });
+        assert_eq!(GasTier::legacy(gwei(20)), GasTier { max_fee_per_gas: gwei(20), max_priority_fee_per_gas: gwei(20) });
+    }
+
+    #[test]
+    fn fees_default_to_the_standard_tier() {
+        let fees = eip1559().fees(&FeeOptions::default()).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(22), max_priority_fee_per_gas: gwei(2) });
+
+        let fees = eip1559().fees(&options(Some(Speed::Fast), None, None)).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(23), max_priority_fee_per_gas: gwei(3) });
+    }
+
+    #[test]
+    fn max_fee_caps_without_fixing_the_price() {
+        let fees = eip1559().fees(&options(None, Some("40"), None)).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(40), max_priority_fee_per_gas: gwei(2) });
+
+        let fees = eip1559().fees(&options(None, Some("10"), None)).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(10), max_priority_fee_per_gas: gwei(2) });
+
+        // with a base fee below the tip, a cap under the tier's tip limits the tip as well
+        let cheap = GasSuggestion { base_fee: Some(gwei(1)), ..eip1559() };
+        let fees = cheap.fees(&options(Some(Speed::Fast), Some("2"), None)).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(2), max_priority_fee_per_gas: gwei(2) });
+    }
+
+    #[test]
+    fn custom_tip_gets_the_same_headroom() {
+        let fees = eip1559().fees(&options(Some(Speed::Slow), None, Some("1.5"))).unwrap();
+        assert_eq!(fees, TxFees::Eip1559 { max_fee_per_gas: gwei(20) + U256::from(GWEI * 3 / 2), max_priority_fee_per_gas: U256::from(GWEI * 3 / 2) });
+    }
+
+    #[test]
+    fn rejects_unmeetable_fees() {
+        let err = eip1559().fees(&options(None, Some("9"), None)).unwrap_err();
+        assert_eq!(err.to_string(), "max_fee_per_gas of 9 gwei is below the next base fee of 10 gwei");
+        let err = eip1559().fees(&options(None, Some("12"), Some("13"))).unwrap_err();
+        assert_eq!(err.to_string(), "max_priority_fee_per_gas exceeds max_fee_per_gas");
+        let err = eip1559().fees(&options(None, Some("lots"), None)).unwrap_err();
+        assert_eq!(err.to_string(), "max_fee_per_gas: \"lots\" is not an amount in gwei");
+    }
+
+    #[test]
+    fn networks_without_a_base_fee_get_a_capped_gas_price() {
+        assert_eq!(legacy().fees(&FeeOptions::default()).unwrap(), TxFees::Legacy { gas_price: gwei(20) });
+        assert_eq!(legacy().fees(&options(None, Some("15"), None)).unwrap(), TxFees::Legacy { gas_price: gwei(15) });
+        assert_eq!(legacy().fees(&options(None, Some("30"), Some("1"))).unwrap(), TxFees::Legacy { gas_price: gwei(20) });
+    }
+
+    #[test]
+    fn apply_sets_the_transaction_type() {
+        let tx = TransactionRequest::new().to(Address::repeat_byte(1)).value(5).gas_price(gwei(99));
+
+        let TypedTransaction::Eip1559(typed) = (TxFees::Eip1559 { max_fee_per_gas: gwei(22), max_priority_fee_per_gas: gwei(2) }).apply(tx.clone()) else {
+            panic!("expected an EIP-1559 transaction");
+        };
+        assert_eq!((typed.max_fee_per_gas, typed.max_priority_fee_per_gas), (Some(gwei(22)), Some(gwei(2))));
+        assert_eq!(typed.value, Some(5.into()));
+
+        let typed = (TxFees::Legacy { gas_price: gwei(20) }).apply(tx);
+        assert!(matches!(typed, TypedTransaction::Legacy(_)));
+        assert_eq!(typed.gas_price(), Some(gwei(20)));
+    }
+}
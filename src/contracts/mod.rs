@@ -1,4 +1,27 @@
+use std::{fs::read_to_string, path::Path};
+
+use ethers::abi::Abi;
+
+use crate::config::config;
 
 pub mod provider;
+pub mod transport;
 pub mod disperse_collect;
-pub mod erc20;
\ No newline at end of file
+pub mod erc20;
+pub mod erc721;
+pub mod erc1155;
+pub mod weth;
+pub mod gas_oracle;
+pub mod token_list;
+pub mod storage;
+pub mod transfer_probe;
+pub mod signer;
+
+/// ABI files every contract wrapper expects to find under the configured ABI directory.
+pub const ABI_NAMES: [&str; 5] = ["disperse_collect", "erc20", "erc721", "erc1155", "weth"];
+
+pub fn load_abi(name: &str) -> Result<Abi, Box<dyn std::error::Error>> {
+    let path = Path::new(&config().abi.dir).join(format!("{name}.json"));
+    let abi = Abi::load(read_to_string(path)?.as_bytes())?;
+    Ok(abi)
+}
@@ -1,18 +1,45 @@
 use ethers::{
-    core::k256::ecdsa::SigningKey, providers::{Http, Middleware, Provider}, signers::Wallet, types::{transaction::eip2718::TypedTransaction, Address, Bytes, Signature, TransactionReceipt, TransactionRequest, H256, U256}, utils::hex
+    abi::Detokenize, contract::Contract, core::k256::ecdsa::SigningKey, providers::{call_raw::{spoof, RawCall}, Middleware, Provider, ProviderError, RpcError}, signers::Wallet, types::{transaction::{eip2718::TypedTransaction, eip712::{Eip712, TypedData}}, Address, BlockNumber, Bytes, FeeHistory, Signature, TransactionReceipt, TransactionRequest, H256, U256, U64}, utils::hex
 };
-use std::sync::Arc;
-use std::convert::TryFrom;
+use std::{fmt, sync::Arc};
+
+use crate::config::config;
+use super::{gas_oracle::TxFees, transport::BatchTransport};
+
+pub type RpcProvider = Provider<BatchTransport>;
+
+/// Call output that doesn't decode as the called function's outputs. Reading the raw word instead
+/// would turn these into zero balances and allowances.
+#[derive(Debug)]
+pub enum CallOutputError {
+    NotAContract(Address),
+    /// The contract has code but returned nothing, e.g. a fallback answering an unknown function
+    Empty { contract: Address, function: String },
+    /// Too short or otherwise not matching the ABI
+    Malformed { contract: Address, function: String, len: usize },
+}
+
+impl fmt::Display for CallOutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAContract(address) => write!(f, "{address:?} is not a contract"),
+            Self::Empty { contract, function } => write!(f, "{contract:?} returned no data for {function}(), it may not implement it"),
+            Self::Malformed { contract, function, len } => write!(f, "{contract:?} returned {len} bytes that are not a valid {function}() result"),
+        }
+    }
+}
+
+impl std::error::Error for CallOutputError {}
 
 pub struct EthProvider {
-    pub provider: Arc<Provider<Http>>,
+    pub provider: Arc<RpcProvider>,
 }
 
 impl EthProvider {
     pub fn new(rpc_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
+        let transport = BatchTransport::shared(rpc_url)?;
         Ok(Self {
-            provider: Arc::new(provider)
+            provider: Arc::new(Provider::new(transport))
         })
     }
 
@@ -21,8 +48,10 @@ impl EthProvider {
         Ok(wallet)
     }
 
+    /// Balance at the latest block, read at that block's number so the transport caches it per block.
     pub async fn get_balance(&self, address: Address) -> Result<U256, Box<dyn std::error::Error>> {
-        let balance = self.provider.get_balance(address, None).await?;
+        let block = self.get_block_number().await?;
+        let balance = self.provider.get_balance(address, Some(BlockNumber::Number(block).into())).await?;
         Ok(balance)
     }
 
@@ -31,9 +60,10 @@ impl EthProvider {
         Ok(nonce)
     }
 
+    /// `eth_estimateGas` with the configured `gas.estimate_multiplier` applied.
     pub async fn estimate_gas(&self, tx: &TransactionRequest) -> Result<U256, Box<dyn std::error::Error>> {
         let gas = self.provider.estimate_gas(&TypedTransaction::Legacy(tx.clone()), None).await?;
-        Ok(gas)
+        Ok(config().gas.with_margin(gas))
     }
 
     pub async fn get_transaction_count(&self, address: Address) -> Result<U256, Box<dyn std::error::Error>> {
@@ -41,11 +71,32 @@ impl EthProvider {
         Ok(count)
     }
 
+    pub async fn get_chain_id(&self) -> Result<U256, Box<dyn std::error::Error>> {
+        let chain_id = self.provider.get_chainid().await?;
+        Ok(chain_id)
+    }
+
+    pub async fn get_code(&self, address: Address) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let code = self.provider.get_code(address, None).await?;
+        Ok(code)
+    }
+
+    pub async fn get_block_number(&self) -> Result<U64, Box<dyn std::error::Error>> {
+        let block_number = self.provider.get_block_number().await?;
+        Ok(block_number)
+    }
+
     pub async fn get_gas_price(&self) -> Result<U256, Box<dyn std::error::Error>> {
         let gas_price = self.provider.get_gas_price().await?;
         Ok(gas_price)
     }
 
+    /// Base fees and priority fee `percentiles` of the last `block_count` blocks, plus the next base fee.
+    pub async fn get_fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<FeeHistory, Box<dyn std::error::Error>> {
+        let history = self.provider.fee_history(block_count, BlockNumber::Latest, percentiles).await?;
+        Ok(history)
+    }
+
     pub async fn get_transaction(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>, Box<dyn std::error::Error>> {
         let receipt = self.provider.get_transaction_receipt(tx_hash).await?;
         Ok(receipt)
@@ -56,11 +107,37 @@ impl EthProvider {
         Ok(result)
     }
 
+    /// `eth_call` against the latest block with `state` applied on top. Not every node supports state overrides.
+    pub async fn query_transaction_with_state(&self, tx: &TypedTransaction, state: &spoof::State) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let result = self.provider.call_raw(tx).state(state).await?;
+        Ok(result)
+    }
+
+    /// `eth_estimateGas` with a state override set, for transactions that depend on an earlier one in the same sequence.
+    pub async fn estimate_gas_with_state(&self, tx: &TransactionRequest, state: &spoof::State) -> Result<U256, Box<dyn std::error::Error>> {
+        let tx = TypedTransaction::Legacy(tx.clone());
+        let gas = self.provider.request("eth_estimateGas", (tx, BlockNumber::Latest, state)).await?;
+        Ok(config().gas.with_margin(gas))
+    }
+
     pub fn sign_transaction(signer: &Wallet<SigningKey>, tx: &TypedTransaction) -> Result<Signature, Box<dyn std::error::Error>> {
         let sign = signer.sign_transaction_sync(tx)?;
         Ok(sign)
     }
 
+    /// Signer of a `personal_sign` signature over `message`.
+    pub fn recover_message_signer(message: &[u8], signature: &Signature) -> Result<Address, Box<dyn std::error::Error>> {
+        let signer = signature.recover(message)?;
+        Ok(signer)
+    }
+
+    /// Signer of an `eth_signTypedData_v4` signature over `typed_data`.
+    pub fn recover_typed_data_signer(typed_data: &TypedData, signature: &Signature) -> Result<Address, Box<dyn std::error::Error>> {
+        let digest = typed_data.encode_eip712()?;
+        let signer = signature.recover(digest)?;
+        Ok(signer)
+    }
+
     pub async fn send_signed_transaction(&self, signed_tx: Vec<u8>) -> Result<Option<H256>, Box<dyn std::error::Error>> {
         self.provider
             .send_raw_transaction(Bytes::from(signed_tx))
@@ -79,22 +156,61 @@ impl EthProvider {
         Ok(tx_hash)
     }
     
-    pub async fn prepare_tx(&self, tx: TransactionRequest, sender: Address) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
-        let gas = self.estimate_gas(&tx).await?;
-        let nonce = self.get_nonce(sender).await?;
-        let gas_price = self.get_gas_price().await?;
-        
-        Ok(tx
-            .gas(gas)
-            .nonce(nonce)
-            .gas_price(gas_price))
-    }
-
-    pub fn create_hex_tx(tx: &TransactionRequest) -> String {
+    pub fn create_ether_transfer_tx(sender: Address, recipient: Address, value: U256) -> TransactionRequest {
+        TransactionRequest::new()
+            .to(recipient)
+            .value(value)
+            .from(sender)
+    }
+
+    /// `tx` paying `fees`, with gas, nonce and chain id filled in. Gas already set on `tx` is kept.
+    pub async fn prepare_tx(&self, tx: TransactionRequest, sender: Address, fees: &TxFees) -> Result<TypedTransaction, Box<dyn std::error::Error>> {
+        // estimated without a price, a sender short of the fee is reported by the balance check
+        // rather than failing the estimate
+        let unpriced = TransactionRequest { gas_price: None, ..tx.clone() };
+        // issued concurrently so the transport sends them as one batch
+        let (gas, nonce, chain_id) = tokio::try_join!(
+            async { match tx.gas { Some(gas) => Ok(gas), None => self.estimate_gas(&unpriced).await } },
+            self.get_nonce(sender),
+            self.get_chain_id(),
+        )?;
+
+        let mut tx = fees.apply(tx);
+        tx.set_gas(gas).set_nonce(nonce).set_chain_id(chain_id.as_u64());
+        Ok(tx)
+    }
+
+    /// Decodes the `output` of calling `function` on `contract` with its ABI. Empty output is a
+    /// [`CallOutputError`] telling apart addresses without code, as is output too short to decode.
+    pub async fn decode_output<D: Detokenize>(&self, contract: &Contract<RpcProvider>, function: &str, output: Bytes) -> Result<D, Box<dyn std::error::Error>> {
+        let address = contract.address();
+        if output.is_empty() {
+            let code = self.get_code(address).await?;
+            return Err(if code.is_empty() {
+                CallOutputError::NotAContract(address)
+            } else {
+                CallOutputError::Empty { contract: address, function: function.to_string() }
+            }.into());
+        }
+        let len = output.len();
+        contract
+            .decode_output(function, output)
+            .map_err(|_| CallOutputError::Malformed { contract: address, function: function.to_string(), len }.into())
+    }
+
+    /// The node's message when `err` is a JSON-RPC error response such as a revert, `None` for
+    /// transport and decoding failures.
+    pub fn rpc_error_message(err: &(dyn std::error::Error + 'static)) -> Option<String> {
+        err.downcast_ref::<ProviderError>()
+            .and_then(|err| err.as_error_response())
+            .map(|err| err.message.clone())
+    }
+
+    pub fn create_hex_tx(tx: &TypedTransaction) -> String {
         format!("0x{}", hex::encode(tx.rlp()))
     }
 
-    pub fn create_hex_tx_from_signed(tx: &TransactionRequest, sign: &Signature) -> String {
+    pub fn create_hex_tx_from_signed(tx: &TypedTransaction, sign: &Signature) -> String {
         format!("0x{}", hex::encode(tx.rlp_signed(sign)))
     }
 }
\ No newline at end of file
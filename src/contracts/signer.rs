@@ -0,0 +1,27 @@
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    signers::{Signer, Wallet},
+    types::{Address, Signature},
+};
+
+/// Server-side signer backed by an encrypted JSON keystore, decrypted once at startup.
+pub struct KeystoreSigner {
+    wallet: Wallet<SigningKey>,
+}
+
+impl KeystoreSigner {
+    pub fn load(path: &str, password: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let wallet = Wallet::<SigningKey>::decrypt_keystore(path, password)?;
+        Ok(Self { wallet })
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// `personal_sign` signature over `message`.
+    pub async fn sign_message(&self, message: &[u8]) -> Result<Signature, Box<dyn std::error::Error>> {
+        let signature = self.wallet.sign_message(message).await?;
+        Ok(signature)
+    }
+}
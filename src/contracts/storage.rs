@@ -0,0 +1,133 @@
+use ethers::{
+    providers::call_raw::spoof,
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use futures::future::join_all;
+
+/// Storage slots probed when looking for a token mapping. Covers OpenZeppelin, Solmate and most
+/// hand-rolled ERC20s, which declare balances and allowances among their first variables.
+const MAX_PROBE_SLOT: u64 = 20;
+
+/// Where a contract keeps a (possibly nested) mapping of addresses: the slot it is declared at and
+/// whether keys are hashed the Vyper way, slot first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MappingSlot {
+    pub slot: U256,
+    pub vyper: bool,
+}
+
+impl MappingSlot {
+    /// Storage key of `mapping[keys[0]][keys[1]]...`.
+    pub fn key(&self, keys: &[Address]) -> H256 {
+        keys.iter().fold(H256::from(<[u8; 32]>::from(self.slot)), |slot, key| {
+            let key = H256::from(*key);
+            let preimage = if self.vyper {
+                [slot.as_bytes(), key.as_bytes()].concat()
+            } else {
+                [key.as_bytes(), slot.as_bytes()].concat()
+            };
+            H256::from(keccak256(preimage))
+        })
+    }
+
+    /// State override setting `mapping[keys...] = value` on `contract`.
+    pub fn override_value(&self, state: &mut spoof::State, contract: Address, keys: &[Address], value: U256) {
+        state.account(contract).store(self.key(keys), H256::from(<[u8; 32]>::from(value)));
+    }
+}
+
+/// Finds the mapping read by `read`: every candidate layout gets a marker value written at `keys`
+/// through a state override, and the layout whose read returns the marker wins. The probes are
+/// issued concurrently so the transport sends them as one batch.
+///
+/// Returns `Ok(None)` when no layout matches and the first error when the node rejected every
+/// probe, which usually means it does not support state overrides.
+pub async fn find_mapping_slot<F, Fut>(contract: Address, keys: &[Address], read: F) -> Result<Option<MappingSlot>, Box<dyn std::error::Error>>
+where
+    F: Fn(spoof::State) -> Fut,
+    Fut: std::future::Future<Output = Result<U256, Box<dyn std::error::Error>>>,
+{
+    let marker = U256::from_big_endian(&keccak256(b"wallet-manager storage probe"));
+    let layouts: Vec<MappingSlot> = (0..MAX_PROBE_SLOT)
+        .flat_map(|slot| [false, true].map(|vyper| MappingSlot { slot: slot.into(), vyper }))
+        .collect();
+
+    let probes = layouts.iter().map(|layout| {
+        let mut state = spoof::state();
+        layout.override_value(&mut state, contract, keys, marker);
+        read(state)
+    });
+    let results = join_all(probes).await;
+
+    let mut errors = vec![];
+    for (layout, result) in layouts.into_iter().zip(results) {
+        match result {
+            Ok(value) if value == marker => return Ok(Some(layout)),
+            Ok(_) => {},
+            Err(err) => errors.push(err),
+        }
+    }
+    if errors.len() == (MAX_PROBE_SLOT * 2) as usize {
+        return Err(errors.swap_remove(0));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::{encode, Token};
+
+    use super::*;
+
+    fn owner() -> Address {
+        "0x1111111111111111111111111111111111111111".parse().unwrap()
+    }
+
+    fn spender() -> Address {
+        "0x2222222222222222222222222222222222222222".parse().unwrap()
+    }
+
+    #[test]
+    fn solidity_keys_hash_the_key_before_the_slot() {
+        let slot = MappingSlot { slot: 3.into(), vyper: false };
+        let balance = H256::from(keccak256(encode(&[Token::Address(owner()), Token::Uint(3.into())])));
+        assert_eq!(slot.key(&[owner()]), balance);
+
+        let allowance = H256::from(keccak256(encode(&[Token::Address(spender()), Token::FixedBytes(balance.as_bytes().to_vec())])));
+        assert_eq!(slot.key(&[owner(), spender()]), allowance);
+    }
+
+    #[test]
+    fn vyper_keys_hash_the_slot_before_the_key() {
+        let slot = MappingSlot { slot: 3.into(), vyper: true };
+        let balance = H256::from(keccak256(encode(&[Token::Uint(3.into()), Token::Address(owner())])));
+        assert_eq!(slot.key(&[owner()]), balance);
+    }
+
+    #[test]
+    fn without_keys_the_key_is_the_slot() {
+        assert_eq!(MappingSlot { slot: 7.into(), vyper: false }.key(&[]), H256::from_low_u64_be(7));
+    }
+
+    #[tokio::test]
+    async fn finds_the_slot_the_contract_reads() {
+        let token = Address::repeat_byte(0xaa);
+        let balances = MappingSlot { slot: 3.into(), vyper: false };
+        let found = find_mapping_slot(token, &[owner()], |mut state| async move {
+            let storage = state.account(token).storage.take().unwrap_or_default();
+            let value = storage.get(&balances.key(&[owner()])).copied().unwrap_or_default();
+            Ok(U256::from_big_endian(value.as_bytes()))
+        }).await.unwrap();
+        assert_eq!(found, Some(balances));
+
+        let not_found = find_mapping_slot(token, &[owner()], |_| async { Ok(U256::zero()) }).await.unwrap();
+        assert_eq!(not_found, None);
+    }
+
+    #[tokio::test]
+    async fn fails_when_every_probe_is_rejected() {
+        let result = find_mapping_slot(Address::zero(), &[owner()], |_| async { Err("state overrides are not supported".into()) }).await;
+        assert_eq!(result.unwrap_err().to_string(), "state overrides are not supported");
+    }
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, fs::read_to_string, sync::Mutex};
+
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::config;
+use super::erc20::ERC20;
+
+/// A token-list document (https://tokenlists.org), only the fields used here.
+#[derive(Deserialize)]
+struct TokenList {
+    name: String,
+    tokens: Vec<TokenInfo>,
+}
+
+/// One token-list entry, serialized in the token-list format.
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    #[schema(example = 1)]
+    pub chain_id: u64,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    pub address: Address,
+    #[schema(example = "Dai Stablecoin")]
+    pub name: String,
+    #[schema(example = "DAI")]
+    pub symbol: String,
+    #[schema(example = 18)]
+    pub decimals: u8,
+    #[serde(rename = "logoURI", skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// The tokens of the selected network from its configured token list. In strict mode only listed
+/// tokens whose on-chain metadata matches the list are accepted.
+pub struct TokenRegistry {
+    name: Option<String>,
+    tokens: Vec<TokenInfo>,
+    strict: bool,
+    /// Outcome of each on-chain check, `Some(reason)` for rejected tokens
+    verified: Mutex<HashMap<Address, Option<String>>>,
+}
+
+impl TokenRegistry {
+    /// Loads the list at `path`, keeping the entries for `chain_id`.
+    pub fn load(path: &str, chain_id: u64, strict: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let list: TokenList = serde_json::from_str(&read_to_string(path)?)?;
+        let mut tokens: Vec<TokenInfo> = vec![];
+        for token in list.tokens.into_iter().filter(|token| token.chain_id == chain_id) {
+            if tokens.iter().any(|listed| listed.address == token.address) {
+                return Err(format!("{:?} is listed more than once for chain {chain_id}", token.address).into());
+            }
+            tokens.push(token);
+        }
+        Ok(Self {
+            name: Some(list.name),
+            tokens,
+            strict,
+            verified: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// A registry without a list, accepting every token.
+    pub fn empty() -> Self {
+        Self {
+            name: None,
+            tokens: vec![],
+            strict: false,
+            verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn get(&self, address: Address) -> Option<&TokenInfo> {
+        self.tokens.iter().find(|token| token.address == address)
+    }
+
+    /// Tokens whose address equals `query` or whose symbol or name contains it, ignoring case.
+    /// Exact symbol matches come first, the rest keep the list's order.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&TokenInfo> {
+        let query = query.trim().to_lowercase();
+        if let Ok(address) = query.parse::<Address>() {
+            return self.get(address).into_iter().collect();
+        }
+        let mut matches: Vec<&TokenInfo> = self.tokens
+            .iter()
+            .filter(|token| token.symbol.to_lowercase().contains(&query) || token.name.to_lowercase().contains(&query))
+            .collect();
+        matches.sort_by_key(|token| token.symbol.to_lowercase() != query);
+        matches.truncate(limit);
+        matches
+    }
+
+    /// `Some(reason)` when strict mode rejects `token`: it is not listed, or its on-chain `decimals`
+    /// or `symbol` disagree with the list. Outcomes are cached, provider errors are not.
+    pub async fn check(&self, token: Address) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if !self.strict {
+            return Ok(None);
+        }
+        let Some(listed) = self.get(token) else {
+            return Ok(Some(format!("Token {token:?} is not on the token list")));
+        };
+        if let Some(outcome) = self.verified.lock().unwrap().get(&token) {
+            return Ok(outcome.clone());
+        }
+
+        let erc20 = ERC20::new(&config().network().rpc_url, token)?;
+        let (decimals, symbol) = tokio::try_join!(erc20.query_decimals(), erc20.query_symbol())?;
+        let outcome = if decimals != U256::from(listed.decimals) {
+            Some(format!("Token {token:?} has {decimals} decimals on-chain but {} in the token list", listed.decimals))
+        } else if symbol != listed.symbol {
+            Some(format!("Token {token:?} has symbol {symbol:?} on-chain but {:?} in the token list", listed.symbol))
+        } else {
+            None
+        };
+        self.verified.lock().unwrap().insert(token, outcome.clone());
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const DAI: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    const SDAI: &str = "0x83F20F44975D03b1b09e64809B757c47f942BEeA";
+
+    fn entry(chain_id: u64, address: &str, name: &str, symbol: &str) -> serde_json::Value {
+        json!({"chainId": chain_id, "address": address, "name": name, "symbol": symbol, "decimals": 18})
+    }
+
+    fn write_list(name: &str, tokens: Vec<serde_json::Value>) -> String {
+        let path = std::env::temp_dir().join(format!("token-list-{name}-{}.json", std::process::id()));
+        std::fs::write(&path, json!({"name": "Test List", "tokens": tokens}).to_string()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn registry(name: &str) -> TokenRegistry {
+        let path = write_list(name, vec![
+            entry(1, SDAI, "Savings Dai", "sDAI"),
+            entry(1, USDC, "USD Coin", "USDC"),
+            entry(1, DAI, "Dai Stablecoin", "DAI"),
+            entry(10, DAI, "Dai Stablecoin", "DAI"),
+        ]);
+        TokenRegistry::load(&path, 1, false).unwrap()
+    }
+
+    #[test]
+    fn keeps_the_entries_of_the_chain() {
+        let registry = registry("chain");
+        assert_eq!(registry.name(), Some("Test List"));
+        assert_eq!(registry.len(), 3);
+        assert!(registry.tokens.iter().all(|token| token.chain_id == 1));
+    }
+
+    #[test]
+    fn rejects_an_address_listed_twice_on_the_chain() {
+        let path = write_list("duplicate", vec![entry(1, DAI, "Dai Stablecoin", "DAI"), entry(1, &DAI.to_lowercase(), "Dai", "DAI")]);
+        let err = TokenRegistry::load(&path, 1, false).err().unwrap();
+        assert_eq!(err.to_string(), "0x6b175474e89094c44da98b954eedeac495271d0f is listed more than once for chain 1");
+    }
+
+    #[test]
+    fn searches_by_symbol_name_and_address() {
+        let registry = registry("search");
+        let symbols = |query: &str, limit: usize| -> Vec<String> {
+            registry.search(query, limit).into_iter().map(|token| token.symbol.clone()).collect()
+        };
+        // the exact symbol first, then the list's order
+        assert_eq!(symbols("dai", 10), vec!["DAI", "sDAI"]);
+        assert_eq!(symbols("dai", 1), vec!["DAI"]);
+        assert_eq!(symbols(" usd coin ", 10), vec!["USDC"]);
+        assert_eq!(symbols(&USDC.to_lowercase(), 10), vec!["USDC"]);
+        assert!(symbols("0x0000000000000000000000000000000000000001", 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_strict_registries_check_tokens() {
+        let unlisted: Address = "0x0000000000000000000000000000000000000001".parse().unwrap();
+        assert_eq!(registry("lenient").check(unlisted).await.unwrap(), None);
+
+        let path = write_list("strict", vec![entry(1, DAI, "Dai Stablecoin", "DAI")]);
+        let strict = TokenRegistry::load(&path, 1, true).unwrap();
+        assert_eq!(
+            strict.check(unlisted).await.unwrap(),
+            Some("Token 0x0000000000000000000000000000000000000001 is not on the token list".to_string()),
+        );
+    }
+}
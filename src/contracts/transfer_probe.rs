@@ -0,0 +1,185 @@
+use ethers::{
+    abi::{decode, encode, ParamType, Token},
+    providers::call_raw::spoof,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256, U512},
+};
+
+use super::erc20::ERC20;
+
+/// Runtime code placed at the sender through a state override. Called with
+/// `abi.encode(token, to, amount)`, it returns `abi.encode(received, spent)` and reverts with the
+/// token's revert data when a call fails. Equivalent to:
+///
+/// ```text
+/// uint256 received = token.balanceOf(to);
+/// uint256 spent = token.balanceOf(address(this));
+/// // no return value is success, like USDT
+/// (, bytes memory result) = token.call(transfer(to, amount));
+/// require(result.length == 0 || abi.decode(result, (bool)), "transfer returned false");
+/// unchecked { received = token.balanceOf(to) - received; }
+/// unchecked { spent = spent - token.balanceOf(address(this)); }
+/// ```
+const TRANSFER_PROBE: &str = "6370a0823160e01b60005260203560045260206080602460006000355afa15610122576370a0823160e01b60005230600452602060a0602460006000355afa156101225763a9059cbb60e01b600052602035600452604035602452600060006044600060006000355af115610122573d156100c1576020600060003e6000516100c1576308c379a060e01b600052602060045260176024527f7472616e736665722072657475726e65642066616c736500000000000000000060445260646000fd5b6370a0823160e01b600052602035600452602060c0602460006000355afa15610122576370a0823160e01b60005230600452602060e0602460006000355afa156101225760805160c051036101005260e05160a05103610120526040610100f35b3d600060003e3d6000fd";
+
+/// Balances moved by one simulated transfer of `amount`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferSimulation {
+    pub amount: U256,
+    /// What the recipient's balance grew by
+    pub received: U256,
+    /// What the sender's balance shrank by
+    pub spent: U256,
+}
+
+impl TransferSimulation {
+    /// Whether the transfer moved exactly `amount`.
+    pub fn is_exact(&self) -> bool {
+        self.received == self.amount && self.spent == self.amount
+    }
+
+    /// Share of `amount` lost on the way, in basis points, rounded up so any loss shows.
+    pub fn fee_bps(&self) -> U256 {
+        let lost = self.amount.saturating_sub(self.received);
+        if self.amount.is_zero() || lost.is_zero() {
+            return U256::zero();
+        }
+        let amount = U512::from(self.amount);
+        // `lost` is at most `amount`, so the result is at most 10 000
+        U256::try_from((lost.full_mul(U256::from(10_000)) + amount - 1) / amount).unwrap_or(U256::from(10_000))
+    }
+}
+
+/// Simulates `from` transferring `amount` of the token to `to` and measures both balances
+/// before and after, catching fee-on-transfer tokens and share-based tokens that round.
+/// A sender holding less than `amount` is topped up through a balance override.
+///
+/// Returns `Ok(None)` when the sender lacks the balance and the token's balance mapping can't be
+/// located, and an error carrying the revert reason when the transfer fails.
+pub async fn simulate_transfer(erc20: &ERC20, from: Address, to: Address, amount: U256) -> Result<Option<TransferSimulation>, Box<dyn std::error::Error>> {
+    let balance = erc20.query_balance_of(from).await?;
+    let mut state = if balance < amount {
+        match erc20.balance_override(&[(from, amount)]).await? {
+            Some(state) => state,
+            None => return Ok(None),
+        }
+    } else {
+        spoof::state()
+    };
+    state.account(from).code(TRANSFER_PROBE.parse::<Bytes>()?);
+
+    let input = encode(&[Token::Address(erc20.contract.address()), Token::Address(to), Token::Uint(amount)]);
+    let tx = TransactionRequest::new().to(from).data(input);
+    let output = erc20.provider.query_transaction_with_state(&TypedTransaction::Legacy(tx), &state).await?;
+    let decoded = decode(&[ParamType::Uint(256), ParamType::Uint(256)], &output)?;
+    match decoded.as_slice() {
+        [Token::Uint(received), Token::Uint(spent)] => Ok(Some(TransferSimulation { amount, received: *received, spent: *spent })),
+        _ => Err("Unexpected transfer probe output".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Address as EvmAddress, Bytecode, Bytes as EvmBytes, ExecutionResult, Output, TxKind, U256 as EvmU256},
+        Evm,
+    };
+
+    use super::*;
+
+    // Minimal tokens keeping each balance at the slot of the holder's address, with `balanceOf` and
+    // a `transfer` that reverts when the balance is short.
+    /// moves `amount` and returns true
+    const STANDARD_TOKEN: &str = "60003560e01c806370a0823114610022578063a9059cbb1461002f575b60006000fd5b6004355460005260206000f35b335460243580821061001c5780910333556127108161000002049003600435540160043555600160005260206000f3";
+    /// moves `amount` less 2% and returns true
+    const FEE_TOKEN: &str = "60003560e01c806370a0823114610022578063a9059cbb1461002f575b60006000fd5b6004355460005260206000f35b335460243580821061001c578091033355612710816100c802049003600435540160043555600160005260206000f3";
+    /// moves `amount` and returns nothing, like USDT
+    const NO_RETURN_TOKEN: &str = "60003560e01c806370a0823114610022578063a9059cbb1461002f575b60006000fd5b6004355460005260206000f35b335460243580821061001c578091033355612710816100000204900360043554016004355500";
+    /// moves nothing and returns false
+    const FALSE_TOKEN: &str = "60003560e01c806370a0823114610022578063a9059cbb1461002f575b60006000fd5b6004355460005260206000f35b600060005260206000f3";
+
+    const BALANCE: u64 = 1_000_000;
+
+    fn address(byte: u8) -> EvmAddress {
+        EvmAddress::repeat_byte(byte)
+    }
+
+    fn account(hex: &str) -> AccountInfo {
+        let code = Bytecode::new_raw(EvmBytes::from(hex.parse::<Bytes>().unwrap().to_vec()));
+        AccountInfo::new(EvmU256::ZERO, 1, code.hash_slow(), code)
+    }
+
+    /// Runs the probe at the sender like the state override does, against `token_code`.
+    fn probe(token_code: &str, amount: u64) -> Result<TransferSimulation, String> {
+        let (token, sender, to) = (address(0xaa), address(0x11), address(0x22));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(token, account(token_code));
+        db.insert_account_info(sender, account(TRANSFER_PROBE));
+        db.insert_account_storage(token, EvmU256::from_be_slice(sender.as_slice()), EvmU256::from(BALANCE)).unwrap();
+
+        let input = encode(&[
+            Token::Address(Address::from_slice(token.as_slice())),
+            Token::Address(Address::from_slice(to.as_slice())),
+            Token::Uint(amount.into()),
+        ]);
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.transact_to = TxKind::Call(sender);
+                tx.data = input.into();
+            })
+            .build();
+        match evm.transact().unwrap().result {
+            ExecutionResult::Success { output: Output::Call(output), .. } => {
+                let decoded = decode(&[ParamType::Uint(256), ParamType::Uint(256)], &output).unwrap();
+                let [Token::Uint(received), Token::Uint(spent)] = decoded.as_slice() else { unreachable!() };
+                Ok(TransferSimulation { amount: amount.into(), received: *received, spent: *spent })
+            },
+            ExecutionResult::Revert { output, .. } if output.starts_with(&[0x08, 0xc3, 0x79, 0xa0]) => {
+                Err(decode(&[ParamType::String], &output[4..]).unwrap()[0].to_string())
+            },
+            result => Err(format!("{result:?}")),
+        }
+    }
+
+    #[test]
+    fn measures_an_exact_transfer() {
+        let simulation = probe(STANDARD_TOKEN, 1000).unwrap();
+        assert!(simulation.is_exact());
+        assert_eq!(simulation.fee_bps(), U256::zero());
+        assert!(probe(NO_RETURN_TOKEN, 1000).unwrap().is_exact());
+    }
+
+    #[test]
+    fn measures_a_fee_on_transfer() {
+        let simulation = probe(FEE_TOKEN, 1000).unwrap();
+        assert_eq!((simulation.received, simulation.spent), (980.into(), 1000.into()));
+        assert_eq!(simulation.fee_bps(), 200.into());
+    }
+
+    #[test]
+    fn reverts_when_transfer_returns_false() {
+        assert_eq!(probe(FALSE_TOKEN, 1000).unwrap_err(), "transfer returned false");
+    }
+
+    #[test]
+    fn reverts_when_transfer_reverts() {
+        assert!(probe(STANDARD_TOKEN, BALANCE + 1).unwrap_err().starts_with("Revert"));
+    }
+
+    #[test]
+    fn fee_bps_rounds_any_loss_up() {
+        let simulation = |amount: u64, received: u64| TransferSimulation { amount: amount.into(), received: received.into(), spent: amount.into() };
+        assert_eq!(simulation(10_000, 9_900).fee_bps(), 100.into());
+        assert_eq!(simulation(1_000_000_000, 999_999_999).fee_bps(), 1.into());
+        assert_eq!(simulation(1_000, 0).fee_bps(), 10_000.into());
+        assert_eq!(simulation(1_000, 1_001).fee_bps(), U256::zero());
+        assert_eq!(simulation(0, 0).fee_bps(), U256::zero());
+
+        let simulation = |amount: U256, received: U256| TransferSimulation { amount, received, spent: amount };
+        assert_eq!(simulation(U256::MAX, U256::MAX - 1).fee_bps(), 1.into());
+        let amount = U256::MAX - U256::MAX % 10_000;
+        assert_eq!(simulation(amount, amount / 100 * 99).fee_bps(), 100.into());
+        assert_eq!(simulation(U256::MAX, U256::zero()).fee_bps(), 10_000.into());
+    }
+}
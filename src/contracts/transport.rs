@@ -0,0 +1,441 @@
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, JsonRpcError, ProviderError, RpcError};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::{debug, warn, Instrument};
+use url::Url;
+
+use crate::config::config;
+use crate::utils::{logging::redact_url, metrics};
+
+const MAX_BATCH_SIZE: usize = 100;
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+// decimals(), symbol() and name() never change for a deployed token
+const METADATA_SELECTORS: [&str; 3] = ["0x313ce567", "0x95d89b41", "0x06fdde03"];
+// version() and DOMAIN_SEPARATOR() change when a proxy is upgraded, the separator also with the chain id
+const DOMAIN_SELECTORS: [&str; 2] = ["0x54fd4d50", "0x3644e515"];
+const DOMAIN_TTL: Duration = Duration::from_secs(60);
+
+static TRANSPORTS: Lazy<Mutex<HashMap<String, BatchTransport>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug)]
+pub enum TransportError {
+    Http(String),
+    JsonRpc(JsonRpcError),
+    Serde { err: serde_json::Error, text: String },
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Http(err) => write!(f, "HTTP error: {err}"),
+            TransportError::JsonRpc(err) => write!(f, "{err}"),
+            TransportError::Serde { err, text } => write!(f, "Deserialization Error: {err}. Response: {text}"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            TransportError::JsonRpc(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            TransportError::Serde { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(src: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(src))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum CachePolicy {
+    Forever,
+    Ttl(Duration),
+}
+
+struct CacheEntry {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+struct PendingCall {
+    id: u64,
+    method: String,
+    params: Value,
+    sender: oneshot::Sender<Result<Value, TransportError>>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    id: u64,
+    result: Option<Value>,
+    error: Option<JsonRpcError>,
+}
+
+struct Inner {
+    id: AtomicU64,
+    client: reqwest::Client,
+    url: Url,
+    batch_window: Duration,
+    gas_price_ttl: Duration,
+    block_number_ttl: Duration,
+    pending: Mutex<Vec<PendingCall>>,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+}
+
+/// JSON-RPC transport that coalesces concurrent calls into batch requests
+/// and caches block-scoped reads.
+#[derive(Clone)]
+pub struct BatchTransport {
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for BatchTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchTransport").field("url", &redact_url(self.inner.url.as_str())).finish()
+    }
+}
+
+impl BatchTransport {
+    pub fn new(url: Url) -> Result<Self, Box<dyn std::error::Error>> {
+        let timeouts = &config().timeouts;
+        let client = reqwest::Client::builder().timeout(timeouts.rpc_timeout()).build()?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                id: AtomicU64::new(1),
+                client,
+                url,
+                batch_window: timeouts.batch_window(),
+                gas_price_ttl: timeouts.gas_price_ttl(),
+                block_number_ttl: timeouts.block_number_ttl(),
+                pending: Mutex::new(vec![]),
+                cache: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Returns the transport for `rpc_url`, shared by every provider using the same endpoint.
+    pub fn shared(rpc_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut transports = TRANSPORTS.lock().unwrap();
+        if let Some(transport) = transports.get(rpc_url) {
+            return Ok(transport.clone());
+        }
+        let transport = Self::new(Url::parse(rpc_url)?)?;
+        transports.insert(rpc_url.to_string(), transport.clone());
+        Ok(transport)
+    }
+
+    fn cache_get(&self, key: &str) -> Option<Value> {
+        let mut cache = self.inner.cache.lock().unwrap();
+        match cache.get(key) {
+            Some(entry) if entry.expires_at.is_none_or(|at| at > Instant::now()) => Some(entry.value.clone()),
+            Some(_) => {
+                cache.remove(key);
+                None
+            },
+            None => None,
+        }
+    }
+
+    fn cache_put(&self, key: String, value: Value, policy: CachePolicy) {
+        let expires_at = match policy {
+            CachePolicy::Forever => None,
+            CachePolicy::Ttl(ttl) => Some(Instant::now() + ttl),
+        };
+        let mut cache = self.inner.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let now = Instant::now();
+            cache.retain(|_, entry| entry.expires_at.is_none_or(|at| at > now));
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+        }
+        cache.insert(key, CacheEntry { value, expires_at });
+    }
+
+    async fn enqueue(&self, method: &str, params: Value) -> Result<Value, TransportError> {
+        let (sender, receiver) = oneshot::channel();
+        let call = PendingCall {
+            id: self.inner.id.fetch_add(1, Ordering::SeqCst),
+            method: method.to_string(),
+            params,
+            sender,
+        };
+
+        let (schedule, full) = {
+            let mut pending = self.inner.pending.lock().unwrap();
+            pending.push(call);
+            (pending.len() == 1, pending.len() >= MAX_BATCH_SIZE)
+        };
+
+        if full {
+            let inner = self.inner.clone();
+            tokio::spawn(async move { flush(inner).await });
+        } else if schedule {
+            let inner = self.inner.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(inner.batch_window).await;
+                flush(inner).await;
+            });
+        }
+
+        receiver
+            .await
+            .unwrap_or_else(|_| Err(TransportError::Http("batch dispatcher dropped the request".to_string())))
+    }
+}
+
+async fn flush(inner: Arc<Inner>) {
+    let calls = std::mem::take(&mut *inner.pending.lock().unwrap());
+    if calls.is_empty() {
+        return;
+    }
+
+    metrics::RPC_BATCHES.inc();
+    metrics::RPC_BATCH_SIZE.observe(calls.len() as f64);
+
+    let payload: Vec<Value> = calls
+        .iter()
+        .map(|call| json!({"jsonrpc": "2.0", "id": call.id, "method": call.method, "params": call.params}))
+        .collect();
+
+    let body = match send_batch(&inner, &payload).await {
+        Ok(body) => body,
+        Err(err) => {
+            for call in calls {
+                let _ = call.sender.send(Err(TransportError::Http(err.clone())));
+            }
+            return;
+        },
+    };
+
+    let responses = match parse_responses(&body) {
+        Ok(responses) => responses,
+        Err(err) => {
+            let text = String::from_utf8_lossy(&body).to_string();
+            for call in calls {
+                let err = serde::de::Error::custom(err.to_string());
+                let _ = call.sender.send(Err(TransportError::Serde { err, text: text.clone() }));
+            }
+            return;
+        },
+    };
+
+    let mut responses: HashMap<u64, RpcResponse> = responses.into_iter().map(|r| (r.id, r)).collect();
+    for call in calls {
+        let result = match responses.remove(&call.id) {
+            Some(RpcResponse { error: Some(error), .. }) => Err(TransportError::JsonRpc(error)),
+            Some(RpcResponse { result, .. }) => Ok(result.unwrap_or(Value::Null)),
+            None => Err(TransportError::Http(format!("missing response for request id {}", call.id))),
+        };
+        let _ = call.sender.send(result);
+    }
+}
+
+async fn send_batch(inner: &Inner, payload: &[Value]) -> Result<Vec<u8>, String> {
+    // single calls are sent unbatched, some nodes reject one-element batches
+    let request = match payload {
+        [single] => inner.client.post(inner.url.as_ref()).json(single),
+        _ => inner.client.post(inner.url.as_ref()).json(payload),
+    };
+    // reqwest puts the URL in its messages, and with it any API key in the path or query
+    let res = request.send().await.map_err(|e| e.without_url().to_string())?;
+    let body = res.bytes().await.map_err(|e| e.without_url().to_string())?;
+    Ok(body.to_vec())
+}
+
+fn parse_responses(body: &[u8]) -> Result<Vec<RpcResponse>, serde_json::Error> {
+    match serde_json::from_slice::<Value>(body)? {
+        value @ Value::Array(_) => serde_json::from_value(value),
+        value => serde_json::from_value(value).map(|response| vec![response]),
+    }
+}
+
+fn is_pinned_block(block: Option<&Value>) -> bool {
+    match block {
+        Some(Value::String(tag)) => tag.starts_with("0x"),
+        Some(Value::Object(id)) => id.contains_key("blockHash") || id.contains_key("blockNumber"),
+        _ => false,
+    }
+}
+
+fn call_data(tx: Option<&Value>) -> &str {
+    tx.and_then(|tx| tx.get("data").or_else(|| tx.get("input")))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// Empty results, such as from an address without code, are not cached: the contract may be
+/// deployed later or the node may be behind.
+fn is_cacheable(value: &Value) -> bool {
+    !value.is_null() && value.as_str() != Some("0x")
+}
+
+fn cache_policy(method: &str, params: &Value, gas_price_ttl: Duration, block_number_ttl: Duration) -> Option<CachePolicy> {
+    let params = params.as_array()?;
+    match method {
+        "eth_chainId" => Some(CachePolicy::Forever),
+        "eth_blockNumber" => Some(CachePolicy::Ttl(block_number_ttl)),
+        "eth_gasPrice" | "eth_feeHistory" => Some(CachePolicy::Ttl(gas_price_ttl)),
+        "eth_getBalance" | "eth_getCode" | "eth_getTransactionCount" | "eth_getStorageAt" => {
+            is_pinned_block(params.last()).then_some(CachePolicy::Forever)
+        },
+        "eth_call" if params.len() <= 2 => {
+            let data = call_data(params.first());
+            if METADATA_SELECTORS.contains(&data) || is_pinned_block(params.get(1)) {
+                Some(CachePolicy::Forever)
+            } else {
+                DOMAIN_SELECTORS.contains(&data).then_some(CachePolicy::Ttl(DOMAIN_TTL))
+            }
+        },
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for BatchTransport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)
+            .map_err(|err| TransportError::Serde { err, text: method.to_string() })?;
+        let params = if params.is_null() { json!([]) } else { params };
+
+        let policy = cache_policy(method, &params, self.inner.gas_price_ttl, self.inner.block_number_ttl);
+        let key = policy.as_ref().map(|_| format!("{method}:{params}"));
+
+        let value = match key.as_deref().and_then(|key| self.cache_get(key)) {
+            Some(value) => {
+                metrics::RPC_CACHE_HITS.with_label_values(&[method]).inc();
+                tracing::info_span!("rpc", method, cached = true).in_scope(|| debug!("RPC call served from cache"));
+                value
+            },
+            None => {
+                if key.is_some() {
+                    metrics::RPC_CACHE_MISSES.with_label_values(&[method]).inc();
+                }
+                let started = Instant::now();
+                let span = tracing::info_span!("rpc", method, cached = false);
+                let result = self.enqueue(method, params).instrument(span.clone()).await;
+                let elapsed = started.elapsed();
+                metrics::observe_rpc(method, elapsed, result.is_ok());
+                span.in_scope(|| match &result {
+                    Ok(_) => debug!(elapsed_ms = elapsed.as_millis() as u64, "RPC call completed"),
+                    Err(err) => warn!(elapsed_ms = elapsed.as_millis() as u64, error = %err, "RPC call failed"),
+                });
+                let value = result?;
+                if let (Some(key), Some(policy)) = (key, policy) {
+                    if is_cacheable(&value) {
+                        self.cache_put(key, value.clone(), policy);
+                    }
+                }
+                value
+            },
+        };
+
+        serde_json::from_value(value.clone())
+            .map_err(|err| TransportError::Serde { err, text: value.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(5);
+    const BLOCK_TTL: Duration = Duration::from_secs(1);
+
+    fn policy(method: &str, params: Value) -> Option<CachePolicy> {
+        cache_policy(method, &params, TTL, BLOCK_TTL)
+    }
+
+    #[test]
+    fn parses_batch_and_single_responses() {
+        let batch = br#"[{"jsonrpc":"2.0","id":2,"result":"0x1"},{"jsonrpc":"2.0","id":1,"error":{"code":3,"message":"execution reverted"}}]"#;
+        let responses = parse_responses(batch).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, 2);
+        assert_eq!(responses[0].result, Some(json!("0x1")));
+        assert_eq!(responses[1].error.as_ref().unwrap().message, "execution reverted");
+
+        let single = parse_responses(br#"{"jsonrpc":"2.0","id":7,"result":null}"#).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].id, 7);
+
+        assert!(parse_responses(b"<html>rate limited</html>").is_err());
+    }
+
+    #[test]
+    fn caches_balances_only_at_a_pinned_block() {
+        let owner = "0x1111111111111111111111111111111111111111";
+        assert_eq!(policy("eth_getBalance", json!([owner, "0x10"])), Some(CachePolicy::Forever));
+        assert_eq!(policy("eth_getBalance", json!([owner, {"blockHash": "0x00"}])), Some(CachePolicy::Forever));
+        assert_eq!(policy("eth_getBalance", json!([owner, "latest"])), None);
+        assert_eq!(policy("eth_getTransactionCount", json!([owner, "pending"])), None);
+    }
+
+    #[test]
+    fn caches_by_method() {
+        assert_eq!(policy("eth_chainId", json!([])), Some(CachePolicy::Forever));
+        assert_eq!(policy("eth_blockNumber", json!([])), Some(CachePolicy::Ttl(BLOCK_TTL)));
+        assert_eq!(policy("eth_gasPrice", json!([])), Some(CachePolicy::Ttl(TTL)));
+        assert_eq!(policy("eth_feeHistory", json!(["0x14", "latest", [10.0]])), Some(CachePolicy::Ttl(TTL)));
+        assert_eq!(policy("eth_estimateGas", json!([{}])), None);
+        assert_eq!(policy("eth_chainId", Value::Null), None);
+    }
+
+    #[test]
+    fn caches_token_metadata_calls_forever() {
+        let token = "0x6b175474e89094c44da98b954eedeac495271d0f";
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": "0x313ce567"}, "latest"])), Some(CachePolicy::Forever));
+        assert_eq!(policy("eth_call", json!([{"to": token, "input": "0x95d89b41"}, "latest"])), Some(CachePolicy::Forever));
+        let balance_of = "0x70a082310000000000000000000000001111111111111111111111111111111111111111";
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": balance_of}, "latest"])), None);
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": balance_of}, "0x10"])), Some(CachePolicy::Forever));
+        // state overrides make the result depend on more than the block
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": "0x313ce567"}, "latest", {}])), None);
+    }
+
+    #[test]
+    fn caches_permit_domain_calls_briefly() {
+        let token = "0x6b175474e89094c44da98b954eedeac495271d0f";
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": "0x54fd4d50"}, "latest"])), Some(CachePolicy::Ttl(DOMAIN_TTL)));
+        assert_eq!(policy("eth_call", json!([{"to": token, "data": "0x3644e515"}, "latest"])), Some(CachePolicy::Ttl(DOMAIN_TTL)));
+    }
+
+    #[test]
+    fn empty_results_are_not_cached() {
+        assert!(!is_cacheable(&json!("0x")));
+        assert!(!is_cacheable(&Value::Null));
+        assert!(is_cacheable(&json!("0x0000000000000000000000000000000000000000000000000000000000000012")));
+        assert!(is_cacheable(&json!("0x1")));
+    }
+}
@@ -0,0 +1,60 @@
+use ethers::{
+    contract::Contract,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, U256},
+};
+
+use super::load_abi;
+use super::provider::{EthProvider, RpcProvider};
+
+/// The wrapped native token (WETH9) of a network.
+pub struct WETH {
+    pub provider: EthProvider,
+    pub contract: Contract<RpcProvider>,
+}
+
+impl WETH {
+    pub fn new(rpc_url: &str, contract_address: Address) -> Result<Self, Box<dyn std::error::Error>> {
+        let provider = EthProvider::new(rpc_url)?;
+        let abi = load_abi("weth")?;
+        let contract = Contract::new(contract_address, abi, provider.provider.clone());
+        Ok(Self {
+            provider,
+            contract,
+        })
+    }
+
+    /// Wraps `amount` wei of ether sent along with the call.
+    pub fn create_deposit_tx(&self, sender: Address, amount: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("deposit", ())?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .value(amount)
+            .from(sender);
+        Ok(tx)
+    }
+
+    /// Unwraps `amount` WETH back into ether.
+    pub fn create_withdraw_tx(&self, sender: Address, amount: U256) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("withdraw", (amount,))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data)
+            .from(sender);
+        Ok(tx)
+    }
+
+    pub fn create_balance_of_tx(&self, owner: Address) -> Result<TransactionRequest, Box<dyn std::error::Error>> {
+        let data = self.contract.encode("balanceOf", (owner,))?;
+        let tx = TransactionRequest::new()
+            .to(self.contract.address())
+            .data(data);
+        Ok(tx)
+    }
+
+    pub async fn query_balance_of(&self, owner: Address) -> Result<U256, Box<dyn std::error::Error>> {
+        let tx: TransactionRequest = self.create_balance_of_tx(owner)?;
+        let result = self.provider.query_transaction(TypedTransaction::Legacy(tx)).await?;
+        self.provider.decode_output(&self.contract, "balanceOf", result).await
+    }
+}
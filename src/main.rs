@@ -2,36 +2,146 @@
 #[macro_use]
 extern crate lazy_static;
 
-use actix_cors::Cors;
-use actix_web::{http::header, web, App, HttpServer};
+use std::time::{Duration, Instant};
+
+use actix_web::{dev::Service, middleware::from_fn, web, App, HttpServer};
+use tracing::{error, info, warn};
+use tracing_actix_web::TracingLogger;
 
 pub mod utils;
 pub mod contracts;
 pub mod routes;
+pub mod auth;
+
+use auth::{api_keys::ApiKeyStore, siwe::SessionStore};
+use clap::Parser;
+use contracts::{disperse_collect::{DeploymentStatus, DisperseCollect}, signer::KeystoreSigner, token_list::TokenRegistry};
+pub use utils::config;
+use utils::config::{config, Cli, Command, Config};
 
-use contracts::disperse_collect::DisperseCollect;
-pub use utils::variables;
-use utils::variables::{check_env, DISPERSE_COLLECT_CONTRACT_ADDRESS, PORT, RPC_PROVIDER_URL};
+/// How long readiness reuses a DisperseCollect deployment check.
+const DEPLOYMENT_CHECK_TTL: Duration = Duration::from_secs(15);
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    check_env();
-
-    HttpServer::new(|| {
-        let cors = Cors::default()
-            .allowed_methods(vec!["GET", "POST"])
-            .allowed_headers(vec![header::CONTENT_TYPE])
-            .max_age(3600);
-        let disperse_collect = DisperseCollect::new(
-                &RPC_PROVIDER_URL,
-                &DISPERSE_COLLECT_CONTRACT_ADDRESS,
-            ).expect("Failed to create DisperseCollect instance");
+    dotenv::dotenv().ok();
+    utils::logging::init();
+
+    let cli = Cli::parse();
+    if let Some(Command::Keys(command)) = &cli.command {
+        return auth::cli::run_keys_command(&cli, command).map_err(|e| std::io::Error::other(e.to_string()));
+    }
+
+    match Config::load(&cli) {
+        Ok(loaded) => config::init(loaded),
+        Err(errors) => {
+            for error in errors.0.iter() {
+                error!("Invalid configuration: {error}");
+            }
+            return Err(std::io::Error::other(errors.to_string()));
+        },
+    }
+    config().log_summary();
+    let network = config().network();
+
+    let disperse_collect = web::Data::new(DisperseCollect::new(
+            &network.rpc_url,
+            &network.disperse_collect_address,
+        ).expect("Failed to create DisperseCollect instance"));
+
+    let deployment = disperse_collect.verify_deployment(&network.disperse_collect_code_hashes()).await;
+    if deployment.is_healthy() {
+        info!(address = ?deployment.address, code_hash = ?deployment.code_hash, owner = ?deployment.owner, "DisperseCollect deployment verified");
+    } else {
+        for problem in deployment.problems.iter() {
+            error!(address = ?deployment.address, "DisperseCollect verification failed: {problem}");
+        }
+        if config().features.strict_startup {
+            return Err(std::io::Error::other("DisperseCollect deployment verification failed, set features.strict_startup = false to start degraded"));
+        }
+        error!("Starting in degraded mode, readiness will report not ready");
+    }
+    let deployment = web::Data::new(DeploymentStatus::new(deployment, network.disperse_collect_code_hashes(), DEPLOYMENT_CHECK_TTL));
+
+    let api_keys = web::Data::new(
+        ApiKeyStore::load(config().auth.keys_file.as_ref(), &config().auth.keys)
+            .map_err(|e| std::io::Error::other(format!("Failed to load API keys: {e}")))?,
+    );
+    let signer = match &config().signer.keystore_path {
+        Some(path) => {
+            let password = std::env::var(&config().signer.password_env).unwrap_or_default();
+            let signer = KeystoreSigner::load(path, &password)
+                .map_err(|e| std::io::Error::other(format!("Failed to decrypt keystore {path}: {e}")))?;
+            info!(address = ?signer.address(), "Loaded keystore signer");
+            Some(web::Data::new(signer))
+        },
+        None => None,
+    };
+
+    let sessions = web::Data::new(SessionStore::new(config().siwe.clone(), network.chain_id));
+
+    let tokens = match &network.token_list {
+        Some(path) => {
+            let chain_id = match network.chain_id {
+                Some(chain_id) => chain_id,
+                None => disperse_collect.provider.get_chain_id().await
+                    .map_err(|e| std::io::Error::other(format!("Failed to query the chain id for the token list: {e}")))?
+                    .as_u64(),
+            };
+            let registry = TokenRegistry::load(path, chain_id, network.token_list_strict)
+                .map_err(|e| std::io::Error::other(format!("Failed to load token list {path}: {e}")))?;
+            info!(list = registry.name(), chain_id, tokens = registry.len(), strict = registry.is_strict(), "Loaded token list");
+            registry
+        },
+        None => TokenRegistry::empty(),
+    };
+    let tokens = web::Data::new(tokens);
+
+    if !config().auth.enabled {
+        warn!("API key authentication is disabled, every route is open");
+    }
+    if config().cors.permissive {
+        warn!("CORS is permissive, any origin may call the API");
+    }
+
+    let mut server = HttpServer::new(move || {
+        let cors = utils::cors::build_cors(&config().cors);
         App::new()
+            .wrap(from_fn(auth::middleware::token_allowlist))
+            .wrap(from_fn(auth::middleware::session_auth))
+            .wrap(from_fn(utils::rate_limit::rate_limit))
+            .wrap(from_fn(auth::middleware::api_key_auth))
+            .wrap(from_fn(utils::rate_limit::auth_failure_limit))
+            .wrap_fn(|req, srv| {
+                let started = Instant::now();
+                let fut = srv.call(req);
+                async move {
+                    let res = fut.await?;
+                    utils::metrics::observe_request(&res, started.elapsed());
+                    Ok(res)
+                }
+            })
             .wrap(cors)
-            .app_data(web::Data::new(disperse_collect))
+            .wrap(TracingLogger::default())
+            .app_data(disperse_collect.clone())
+            .app_data(deployment.clone())
+            .app_data(api_keys.clone())
+            .app_data(sessions.clone())
+            .app_data(tokens.clone())
+            .configure(|cfg| {
+                if let Some(signer) = &signer {
+                    cfg.app_data(signer.clone());
+                }
+            })
             .service(routes::routes())
     })
-    .bind(("0.0.0.0", *PORT))?
-    .run()
-    .await
+    .client_request_timeout(config().timeouts.client_request_timeout());
+    if let Some(workers) = config().server.workers {
+        server = server.workers(workers);
+    }
+
+    server
+        .bind((config().server.bind_address.as_str(), config().server.port))?
+        .run()
+        .await
 }
\ No newline at end of file
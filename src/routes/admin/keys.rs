@@ -0,0 +1,50 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::auth::api_keys::{ApiKeyStore, ApiScope};
+use crate::routes::responses::{ErrorResponse, IssuedKeyResponse, KeyListResponse, SuccessResponse};
+
+#[derive(Deserialize, Serialize)]
+struct IssueKeyRequest {
+    name: String,
+    scopes: Vec<ApiScope>,
+}
+
+#[get("/keys")]
+async fn list_keys(store: web::Data<ApiKeyStore>) -> impl Responder {
+    HttpResponse::Ok().json(KeyListResponse::new(store.list()))
+}
+
+#[post("/keys")]
+async fn issue_key(req: web::Json<IssueKeyRequest>, store: web::Data<ApiKeyStore>) -> impl Responder {
+    if req.name.is_empty() || req.scopes.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("name and scopes are required"));
+    }
+
+    match store.issue(&req.name, req.scopes.clone()) {
+        Ok((key, record)) => {
+            info!(id = %record.id, name = %record.name, scopes = ?record.scopes, "Issued API key");
+            HttpResponse::Ok().json(IssuedKeyResponse::new(key, record))
+        },
+        Err(err) => {
+            error!(error = ?err, "Failed to issue API key");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to issue API key"))
+        },
+    }
+}
+
+#[post("/keys/{id}/revoke")]
+async fn revoke_key(id: web::Path<String>, store: web::Data<ApiKeyStore>) -> impl Responder {
+    match store.revoke(&id) {
+        Ok(true) => {
+            info!(id = %id, "Revoked API key");
+            HttpResponse::Ok().json(SuccessResponse::default())
+        },
+        Ok(false) => HttpResponse::NotFound().json(ErrorResponse::new("API key not found")),
+        Err(err) => {
+            error!(error = ?err, "Failed to revoke API key");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to revoke API key"))
+        },
+    }
+}
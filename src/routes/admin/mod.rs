@@ -0,0 +1,10 @@
+use actix_web::{web, Scope};
+
+mod keys;
+
+pub fn route() -> Scope {
+    web::scope("/admin")
+        .service(keys::list_keys)
+        .service(keys::issue_key)
+        .service(keys::revoke_key)
+}
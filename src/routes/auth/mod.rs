@@ -0,0 +1,19 @@
+use actix_web::{web, Scope};
+use utoipa::OpenApi;
+
+mod siwe;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    siwe::nonce,
+    siwe::login,
+    siwe::logout,
+))]
+pub struct ApiDoc;
+
+pub fn route() -> Scope {
+    web::scope("/auth")
+        .service(siwe::nonce)
+        .service(siwe::login)
+        .service(siwe::logout)
+}
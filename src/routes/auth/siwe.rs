@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use ethers::types::Signature;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::auth::siwe::{SessionStore, SESSION_HEADER};
+use crate::routes::responses::{ErrorResponse, NonceResponse, SessionResponse, SuccessResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct LoginRequest {
+    /// EIP-4361 message containing a nonce from `/auth/nonce`
+    #[schema(example = "app.example.com wants you to sign in with your Ethereum account:\n0x1111111111111111111111111111111111111111\n\nURI: https://app.example.com\nVersion: 1\nChain ID: 1\nNonce: 8f3a61c0d2b94e7aa1c5f0e9b6d4c2a1\nIssued At: 2025-01-01T00:00:00Z")]
+    message: String,
+    /// `personal_sign` signature of `message`
+    #[schema(example = json!("0x5f1c...1b"))]
+    signature: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/nonce",
+    tag = "auth",
+    description = "Issue a single-use nonce for a Sign-In with Ethereum (EIP-4361) message",
+    responses(
+        (status = 200, description = "Nonce, valid for `siwe.nonce_ttl_secs`", body = NonceResponse),
+    ),
+    security(()),
+)]
+#[get("/nonce")]
+async fn nonce(sessions: web::Data<SessionStore>) -> impl Responder {
+    HttpResponse::Ok().json(NonceResponse::new(sessions.issue_nonce()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    description = "Verify a signed Sign-In with Ethereum message and open a session. Send the token in the `X-Session-Token` header; `/wallet`, `/weth` and `/token` then only accept requests whose `sender` is the signed-in address",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Session token", body = SessionResponse),
+        (status = 400, description = "Invalid signature", body = ErrorResponse),
+        (status = 401, description = "The message is invalid, expired or not signed by its address", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[post("/login")]
+async fn login(req: web::Json<LoginRequest>, sessions: web::Data<SessionStore>) -> impl Responder {
+    let signature = match Signature::from_str(&req.signature) {
+        Ok(signature) => signature,
+        Err(err) => {
+            warn!(error = ?err, "Failed to parse signature");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse signature"));
+        },
+    };
+
+    match sessions.login(&req.message, &signature) {
+        Ok((token, session)) => {
+            info!(address = ?session.address, expires_at = session.expires_at, "Signed in with Ethereum");
+            HttpResponse::Ok().json(SessionResponse::new(token, session.address, session.expires_at))
+        },
+        Err(message) => {
+            warn!(reason = %message, "Rejected sign-in");
+            HttpResponse::Unauthorized().json(ErrorResponse::new(message))
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    description = "End the session in the `X-Session-Token` header",
+    responses(
+        (status = 200, description = "Session ended", body = SuccessResponse),
+        (status = 401, description = "No such session", body = ErrorResponse),
+    ),
+    security(("session" = [])),
+)]
+#[post("/logout")]
+async fn logout(req: HttpRequest, sessions: web::Data<SessionStore>) -> impl Responder {
+    let token = req.headers().get(SESSION_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    if sessions.logout(token.trim()) {
+        HttpResponse::Ok().json(SuccessResponse::default())
+    } else {
+        HttpResponse::Unauthorized().json(ErrorResponse::new("Invalid or expired session"))
+    }
+}
@@ -0,0 +1,181 @@
+use actix_web::HttpResponse;
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, U256};
+use tracing::error;
+
+use crate::contracts::{provider::{CallOutputError, EthProvider}, erc20::ERC20, erc1155::ERC1155, weth::WETH};
+use crate::utils::web3::ether_to_string;
+use crate::routes::responses::{ErrorResponse, InsufficientFundsResponse};
+
+/// Worst-case fee of `tx`: gas limit times gas price, or times the max fee for EIP-1559 transactions.
+pub fn max_fee(tx: &TypedTransaction) -> U256 {
+    let gas = tx.gas().copied().unwrap_or_default();
+    let gas_price = match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas,
+        _ => tx.gas_price(),
+    };
+    gas * gas_price.unwrap_or_default()
+}
+
+pub async fn check_balance(provider: &EthProvider, address: Address, required_balance: U256) -> Result<(), HttpResponse> {
+    let balance = provider.get_balance(address).await.map_err(|err| {
+        error!(error = ?err, "Failed to query balance");
+        HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"))
+    })?;
+
+    if balance < required_balance {
+        return Err(HttpResponse::BadRequest().json(InsufficientFundsResponse::new(
+            "Insufficient balance",
+            ether_to_string(required_balance),
+            ether_to_string(balance),
+            ether_to_string(required_balance - balance),
+        )));
+    }
+
+    Ok(())
+}
+
+/// The sender of a prepared `tx` must hold its value plus the maximum fee. Token operations
+/// carry no value but still need ether for gas.
+pub async fn check_balance_for_tx(provider: &EthProvider, address: Address, tx: &TypedTransaction) -> Result<(), HttpResponse> {
+    let balance = provider.get_balance(address).await.map_err(|err| {
+        error!(error = ?err, "Failed to query balance");
+        HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"))
+    })?;
+
+    let fee = max_fee(tx);
+    let required_balance = tx.value().copied().unwrap_or_default() + fee;
+    if balance < required_balance {
+        let message = if fee >= required_balance {
+            format!("Insufficient balance to pay for gas ({} ether)", ether_to_string(fee))
+        } else {
+            format!("Insufficient balance to cover value plus gas ({} ether)", ether_to_string(fee))
+        };
+        return Err(HttpResponse::BadRequest().json(InsufficientFundsResponse::new(
+            message,
+            ether_to_string(required_balance),
+            ether_to_string(balance),
+            ether_to_string(required_balance - balance),
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn check_token_balance(erc20: &ERC20, owner: Address, required_balance: U256) -> Result<(), HttpResponse> {
+    let balance = erc20.query_balance_of(owner).await.map_err(query_failed)?;
+
+    if balance < required_balance {
+        return Err(insufficient_tokens(erc20, "Insufficient token balance", required_balance, balance).await);
+    }
+
+    Ok(())
+}
+
+/// WETH always has 18 decimals, so amounts are reported in ether.
+pub async fn check_weth_balance(weth: &WETH, owner: Address, required_balance: U256) -> Result<(), HttpResponse> {
+    let balance = weth.query_balance_of(owner).await.map_err(query_failed)?;
+
+    if balance < required_balance {
+        return Err(HttpResponse::BadRequest().json(InsufficientFundsResponse::new(
+            "Insufficient WETH balance",
+            ether_to_string(required_balance),
+            ether_to_string(balance),
+            ether_to_string(required_balance - balance),
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn check_allowance(erc20: &ERC20, owner: Address, spender: Address, required_allowance: U256) -> Result<(), HttpResponse> {
+    let allowance = erc20.query_allowance(owner, spender).await.map_err(query_failed)?;
+
+    if allowance < required_allowance {
+        return Err(insufficient_tokens(erc20, "Insufficient allowance", required_allowance, allowance).await);
+    }
+
+    Ok(())
+}
+
+/// `owner` must hold `amounts[i]` of every token `ids[i]`. Repeated ids are summed. ERC1155
+/// amounts have no decimals and are reported as plain integers.
+pub async fn check_token1155_balances(erc1155: &ERC1155, owner: Address, ids: &[U256], amounts: &[U256]) -> Result<(), HttpResponse> {
+    let Some(required) = sum_by_id(ids, amounts) else {
+        return Err(HttpResponse::BadRequest().json(ErrorResponse::new("amounts overflow")));
+    };
+
+    let balances = erc1155
+        .query_balance_of_batch(vec![owner; required.len()], required.iter().map(|(id, _)| *id).collect())
+        .await
+        .map_err(query_failed)?;
+    for ((id, required), balance) in required.into_iter().zip(balances) {
+        if balance < required {
+            return Err(HttpResponse::BadRequest().json(InsufficientFundsResponse::new(
+                format!("Insufficient balance of token {id}"),
+                required.to_string(),
+                balance.to_string(),
+                (required - balance).to_string(),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// `amounts` summed per id in order of first appearance, `None` when a sum overflows.
+fn sum_by_id(ids: &[U256], amounts: &[U256]) -> Option<Vec<(U256, U256)>> {
+    let mut required: Vec<(U256, U256)> = vec![];
+    for (id, amount) in ids.iter().zip(amounts) {
+        match required.iter_mut().find(|(required_id, _)| required_id == id) {
+            Some((_, total)) => *total = total.checked_add(*amount)?,
+            None => required.push((*id, *amount)),
+        }
+    }
+    Some(required)
+}
+
+/// Output that doesn't decode, such as from an address without code, means the request named the
+/// wrong contract.
+fn query_failed(err: Box<dyn std::error::Error>) -> HttpResponse {
+    if err.is::<CallOutputError>() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string()));
+    }
+    error!(error = ?err, "Failed to query token");
+    HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query token"))
+}
+
+async fn insufficient_tokens(erc20: &ERC20, message: &str, required: U256, available: U256) -> HttpResponse {
+    let amounts = tokio::try_join!(
+        erc20.token_to_string(required),
+        erc20.token_to_string(available),
+        erc20.token_to_string(required - available),
+    );
+    match amounts {
+        Ok((required, available, shortfall)) => {
+            HttpResponse::BadRequest().json(InsufficientFundsResponse::new(message, required, available, shortfall))
+        },
+        Err(_) => HttpResponse::BadRequest().json(ErrorResponse::new(message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_repeated_ids() {
+        let ids = [U256::from(1), U256::from(2), U256::from(1)];
+        let amounts = [U256::from(5), U256::from(7), U256::from(3)];
+        assert_eq!(sum_by_id(&ids, &amounts), Some(vec![(U256::from(1), U256::from(8)), (U256::from(2), U256::from(7))]));
+    }
+
+    #[test]
+    fn repeated_ids_overflowing_are_rejected() {
+        let ids = [U256::from(1), U256::from(1)];
+        let amounts = [U256::one() << 255, U256::one() << 255];
+        assert_eq!(sum_by_id(&ids, &amounts), None);
+
+        let amounts = [U256::MAX - 1, U256::one()];
+        assert_eq!(sum_by_id(&ids, &amounts), Some(vec![(U256::from(1), U256::MAX)]));
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, web, HttpResponse, Responder, Scope};
+use serde_json::json;
+use tracing::warn;
+
+use crate::contracts::{disperse_collect::{DeploymentStatus, DisperseCollect}, load_abi, ABI_NAMES};
+use crate::config::config;
+use crate::routes::responses::{ReadinessResponse, Status, SuccessResponse};
+
+pub fn route() -> Scope {
+    web::scope("/health")
+        .service(liveness)
+        .service(readiness)
+}
+
+/// Logs the details of a failed provider call, the endpoint is public so its body only says what failed.
+fn rpc_failed(check: &str, err: Box<dyn std::error::Error>) -> String {
+    warn!(check, error = %err, "Readiness RPC call failed");
+    "RPC call failed".to_string()
+}
+
+#[get("")]
+async fn liveness() -> impl Responder {
+    HttpResponse::Ok().json(SuccessResponse::default())
+}
+
+#[get("/ready")]
+async fn readiness(disperse_collect: web::Data<DisperseCollect>, deployment: web::Data<DeploymentStatus>) -> impl Responder {
+    let provider = &disperse_collect.provider;
+    let contract_address = disperse_collect.contract.address();
+    let (block_number, chain_id, code, deployment) = tokio::join!(
+        provider.get_block_number(),
+        provider.get_chain_id(),
+        provider.get_code(contract_address),
+        deployment.current(&disperse_collect),
+    );
+
+    let rpc = block_number
+        .map(|block_number| json!({"block_number": block_number.as_u64()}))
+        .map_err(|e| rpc_failed("rpc", e));
+
+    let chain_id = chain_id.map_err(|e| rpc_failed("chain_id", e)).and_then(|chain_id| match config().network().chain_id {
+        Some(expected) if chain_id != expected.into() => {
+            Err(format!("Chain id {chain_id} does not match configured {expected}"))
+        },
+        _ => Ok(json!({"chain_id": chain_id.as_u64()})),
+    });
+
+    let disperse_collect_code = code.map_err(|e| rpc_failed("disperse_collect_code", e)).and_then(|code| {
+        if code.is_empty() {
+            Err(format!("No bytecode at {contract_address:?}"))
+        } else {
+            Ok(json!({"address": contract_address, "code_size": code.len()}))
+        }
+    });
+
+    let abi_errors: Vec<String> = ABI_NAMES
+        .iter()
+        .filter_map(|name| load_abi(name).err().map(|e| {
+            warn!(abi = name, error = %e, "Failed to load ABI");
+            format!("{name}: failed to load")
+        }))
+        .collect();
+    let abi = if abi_errors.is_empty() {
+        Ok(json!(ABI_NAMES))
+    } else {
+        Err(abi_errors.join("; "))
+    };
+
+    let deployment = if deployment.is_healthy() {
+        Ok(json!({"code_hash": deployment.code_hash, "owner": deployment.owner}))
+    } else {
+        Err(deployment.problems.join("; "))
+    };
+
+    let checks: BTreeMap<&str, _> = [
+        ("rpc", rpc),
+        ("chain_id", chain_id),
+        ("disperse_collect_code", disperse_collect_code),
+        ("abi", abi),
+        ("deployment", deployment),
+    ]
+    .into_iter()
+    .map(|(name, result)| (name, result.into()))
+    .collect();
+
+    let res = ReadinessResponse::new(checks);
+    if res.status == Status::Success {
+        HttpResponse::Ok().json(res)
+    } else {
+        let failed: Vec<&str> = res.checks.iter().filter(|(_, check)| !check.ok).map(|(name, _)| *name).collect();
+        warn!(failed = ?failed, "Readiness check failed");
+        HttpResponse::ServiceUnavailable().json(res)
+    }
+}
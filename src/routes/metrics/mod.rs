@@ -0,0 +1,33 @@
+use actix_web::{get, web, HttpResponse, Responder, Scope};
+use tracing::error;
+
+use crate::contracts::disperse_collect::DisperseCollect;
+use crate::utils::metrics::{self, BLOCK_HEIGHT, GAS_PRICE};
+use crate::routes::responses::ErrorResponse;
+
+pub fn route() -> Scope {
+    web::scope("/metrics")
+        .service(metrics_handler)
+}
+
+#[get("")]
+async fn metrics_handler(disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    match disperse_collect.provider.get_gas_price().await {
+        Ok(gas_price) => GAS_PRICE.set(gas_price.low_u64() as i64),
+        Err(err) => error!(error = ?err, "Failed to get gas price"),
+    }
+    match disperse_collect.provider.get_block_number().await {
+        Ok(block_number) => BLOCK_HEIGHT.set(block_number.as_u64() as i64),
+        Err(err) => error!(error = ?err, "Failed to get block number"),
+    }
+
+    match metrics::encode() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body),
+        Err(err) => {
+            error!(error = ?err, "Failed to encode metrics");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to encode metrics"))
+        },
+    }
+}
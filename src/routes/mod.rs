@@ -1,15 +1,46 @@
 use actix_web::Scope;
 
+use crate::config::config;
+
 
 mod wallet;
 mod token;
+mod token721;
+mod token1155;
+mod weth;
 mod web3;
+mod metrics;
+mod health;
+mod admin;
+mod auth;
+pub mod openapi;
+pub mod responses;
+mod checks;
+mod pipeline;
 
 
 pub fn routes() -> Vec<Scope> {
-    vec![
+    let mut scopes = vec![
         wallet::route(),
         token::route(),
+        token721::route(),
+        token1155::route(),
         web3::route(),
-    ]
+        health::route(),
+    ];
+    scopes.extend(openapi::routes());
+    if config().network().weth_address.is_some() {
+        scopes.push(weth::route());
+    }
+    // without authentication anyone could issue themselves an admin key
+    if config().auth.enabled {
+        scopes.push(admin::route());
+    }
+    if config().siwe.enabled {
+        scopes.push(auth::route());
+    }
+    if config().features.metrics {
+        scopes.push(metrics::route());
+    }
+    scopes
 }
\ No newline at end of file
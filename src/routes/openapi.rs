@@ -0,0 +1,66 @@
+use actix_web::{get, web, HttpResponse, Responder, Scope};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+use crate::auth::{middleware::API_KEY_HEADER, siwe::SESSION_HEADER};
+use super::responses::{ErrorResponse, InsufficientFundsResponse, RateLimitedResponse, Status};
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+        components.add_security_scheme("bearer", SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()));
+        components.add_security_scheme("session", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(SESSION_HEADER))));
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Wallet Manager Backend", description = "Builds unsigned transactions for the DisperseCollect contract and ERC20 tokens"),
+    components(schemas(ErrorResponse, InsufficientFundsResponse, RateLimitedResponse, Status)),
+    modifiers(&SecuritySchemes),
+    security(("api_key" = []), ("bearer" = [])),
+    tags(
+        (name = "wallet", description = "Disperse and collect ether or tokens through DisperseCollect"),
+        (name = "token", description = "ERC20 queries and transactions"),
+        (name = "token721", description = "ERC721 queries, transfers and bulk distribution"),
+        (name = "token1155", description = "ERC1155 balances and batch transfers"),
+        (name = "weth", description = "Wrap and unwrap ether through the network's WETH, available when `weth_address` is set"),
+        (name = "web3", description = "Signing, broadcasting and gas fee suggestions"),
+        (name = "auth", description = "Sign-In with Ethereum sessions, required on `/wallet`, `/weth` and `/token` when `siwe.enabled`"),
+    ),
+)]
+struct ApiDoc;
+
+/// The merged document for every documented scope.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.merge(super::wallet::ApiDoc::openapi());
+    doc.merge(super::token::ApiDoc::openapi());
+    doc.merge(super::token721::ApiDoc::openapi());
+    doc.merge(super::token1155::ApiDoc::openapi());
+    doc.merge(super::weth::ApiDoc::openapi());
+    doc.merge(super::web3::ApiDoc::openapi());
+    doc.merge(super::auth::ApiDoc::openapi());
+    doc
+}
+
+pub fn routes() -> Vec<Scope> {
+    vec![
+        web::scope("/openapi.json").service(spec),
+        // Swagger UI assets are bundled into the binary, `/docs` redirects so their relative paths resolve
+        web::scope("/docs")
+            .service(web::redirect("", "/docs/"))
+            .service(SwaggerUi::new("/{_:.*}").config(Config::from("/openapi.json"))),
+    ]
+}
+
+#[get("")]
+async fn spec() -> impl Responder {
+    HttpResponse::Ok().json(openapi())
+}
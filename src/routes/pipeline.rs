@@ -0,0 +1,254 @@
+use std::error::Error;
+
+use actix_web::HttpResponse;
+use ethers::{
+    providers::call_raw::spoof,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, U256},
+};
+use futures::future::join_all;
+use tracing::{error, warn};
+
+use crate::config::config;
+use crate::contracts::{erc20::ERC20, gas_oracle::{FeeOptions, GasOracle, InvalidFeeOptions, TxFees}, provider::{CallOutputError, EthProvider}};
+use crate::utils::metrics;
+use super::checks::{check_balance, check_balance_for_tx, max_fee};
+use super::responses::{ErrorResponse, UnsignedTx, UnsignedTxResponse, UnsignedTxSequenceResponse};
+
+/// The part of every tx-building endpoint after its request-specific checks:
+/// build → simulate → prepare → check gas funds → encode.
+///
+/// `tx` is the result of one of the `create_*_tx` builders. The transaction is simulated with
+/// `eth_call` first so a reverting call is reported as a 400 with the revert reason instead of
+/// failing later in gas estimation. The transaction pays the fees `fees` resolve to, and once gas
+/// and fees are known the sender must be able to pay for value plus the most the fee can be.
+pub async fn build_unsigned_tx(
+    provider: &EthProvider,
+    endpoint: &str,
+    sender: Address,
+    fees: &FeeOptions,
+    tx: Result<TransactionRequest, Box<dyn Error>>,
+) -> HttpResponse {
+    let fees = match resolve_fees(endpoint, fees).await {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    let tx = match prepare_unsigned_tx(provider, endpoint, sender, &fees, tx).await {
+        Ok(tx) => tx,
+        Err(err) => return err,
+    };
+
+    let tx_hex = EthProvider::create_hex_tx(&tx);
+    metrics::record_tx_built(endpoint);
+    HttpResponse::Ok().json(UnsignedTxResponse::new(tx, tx_hex))
+}
+
+/// [`build_unsigned_tx`] for ERC20 functions returning `bool`. Some tokens return `false` instead of
+/// reverting, which a plain simulation takes for success; tokens returning nothing at all, like
+/// USDT, are accepted.
+pub async fn build_unsigned_erc20_tx(
+    erc20: &ERC20,
+    endpoint: &str,
+    sender: Address,
+    fees: &FeeOptions,
+    function: &str,
+    tx: Result<TransactionRequest, Box<dyn Error>>,
+) -> HttpResponse {
+    if let Ok(call) = &tx {
+        if let Err(err) = check_erc20_call(erc20, endpoint, sender, function, call).await {
+            return err;
+        }
+    }
+    build_unsigned_tx(&erc20.provider, endpoint, sender, fees, tx).await
+}
+
+/// The ERC20 check of [`build_unsigned_erc20_tx`], for endpoints preparing the transaction
+/// themselves: a `call` returning `false` or malformed data is a 400.
+pub async fn check_erc20_call(erc20: &ERC20, endpoint: &str, sender: Address, function: &str, call: &TransactionRequest) -> Result<(), HttpResponse> {
+    match erc20.simulate_bool_call(function, call.clone()).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            warn!(endpoint, sender = ?sender, function, "Token call returned false");
+            Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Transaction would fail: {function} returned false"))))
+        },
+        Err(err) if err.is::<CallOutputError>() => {
+            warn!(endpoint, sender = ?sender, error = %err, "Token call returned malformed data");
+            Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Transaction would fail: {err}"))))
+        },
+        // reverts and provider errors are reported by the simulation in `prepare_unsigned_tx`
+        Err(_) => Ok(()),
+    }
+}
+
+/// [`build_unsigned_tx`] without the encoding and with resolved fees, for endpoints returning more
+/// than one transaction or pinning the fees they check.
+pub async fn prepare_unsigned_tx(
+    provider: &EthProvider,
+    endpoint: &str,
+    sender: Address,
+    fees: &TxFees,
+    tx: Result<TransactionRequest, Box<dyn Error>>,
+) -> Result<TypedTransaction, HttpResponse> {
+    let tx = match tx {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!(error = ?err, endpoint, "Failed to build transaction");
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to build transaction")));
+        },
+    };
+
+    if let Err(err) = provider.query_transaction(TypedTransaction::Legacy(tx.clone())).await {
+        return Err(match EthProvider::rpc_error_message(err.as_ref()) {
+            Some(reason) => {
+                warn!(endpoint, sender = ?sender, reason = %reason, "Transaction simulation reverted");
+                HttpResponse::BadRequest().json(ErrorResponse::new(format!("Transaction would fail: {reason}")))
+            },
+            None => {
+                error!(error = ?err, endpoint, "Failed to simulate transaction");
+                HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate transaction"))
+            },
+        });
+    }
+
+    let tx = match provider.prepare_tx(tx, sender, fees).await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!(error = ?err, endpoint, "Failed to prepare transaction");
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to prepare transaction")));
+        },
+    };
+
+    check_balance_for_tx(provider, sender, &tx).await?;
+    Ok(tx)
+}
+
+/// [`build_unsigned_tx`] for several independent transactions from `sender`, returned in order
+/// with consecutive nonces and the same fees.
+///
+/// Every transaction is simulated against the current state, so none may depend on an earlier
+/// one in the sequence. The sender must be able to pay for all of their values and fees.
+pub async fn build_unsigned_tx_sequence(
+    provider: &EthProvider,
+    endpoint: &str,
+    sender: Address,
+    fees: &FeeOptions,
+    txs: Vec<Result<TransactionRequest, Box<dyn Error>>>,
+) -> HttpResponse {
+    let fees = match resolve_fees(endpoint, fees).await {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    // issued concurrently so the transport batches the simulations and estimates
+    let prepared = join_all(txs.into_iter().map(|tx| prepare_unsigned_tx(provider, endpoint, sender, &fees, tx))).await;
+    let mut txs = vec![];
+    for tx in prepared {
+        match tx {
+            Ok(tx) => txs.push(tx),
+            Err(err) => return err,
+        }
+    }
+    let Some(first) = txs.first() else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("No transactions to build"));
+    };
+
+    let nonce = first.nonce().copied().unwrap_or_default();
+    for (i, tx) in txs.iter_mut().enumerate() {
+        tx.set_nonce(nonce + i);
+    }
+    encode_tx_sequence(provider, endpoint, sender, txs).await
+}
+
+/// Appends `tx` to the prepared `txs` when it depends on them, e.g. on their permits or deposits.
+///
+/// `tx` is simulated and estimated with `state` standing in for what the earlier transactions
+/// change, since they are not mined yet. It takes the next nonce and pays `fees`, which should be
+/// the fees `txs` were prepared with. The sender must be able to pay for all of their values and
+/// fees.
+pub async fn build_unsigned_tx_after(
+    provider: &EthProvider,
+    endpoint: &str,
+    sender: Address,
+    fees: &TxFees,
+    mut txs: Vec<TypedTransaction>,
+    tx: Result<TransactionRequest, Box<dyn Error>>,
+    state: &spoof::State,
+) -> HttpResponse {
+    let tx = match tx {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!(error = ?err, endpoint, "Failed to build transaction");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to build transaction"));
+        },
+    };
+    let Some(first) = txs.first() else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("No transactions to build"));
+    };
+    let (nonce, chain_id) = (first.nonce().copied().unwrap_or_default(), first.chain_id());
+
+    if let Err(err) = provider.query_transaction_with_state(&TypedTransaction::Legacy(tx.clone()), state).await {
+        return match EthProvider::rpc_error_message(err.as_ref()) {
+            Some(reason) => {
+                warn!(endpoint, sender = ?sender, reason = %reason, "Transaction simulation reverted");
+                HttpResponse::BadRequest().json(ErrorResponse::new(format!("Transaction would fail: {reason}")))
+            },
+            None => {
+                error!(error = ?err, endpoint, "Failed to simulate transaction");
+                HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate transaction"))
+            },
+        };
+    }
+    let gas = match provider.estimate_gas_with_state(&tx, state).await {
+        Ok(gas) => gas,
+        Err(err) => {
+            error!(error = ?err, endpoint, "Failed to estimate gas");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to estimate gas"));
+        },
+    };
+
+    let mut tx = fees.apply(tx.gas(gas));
+    if let Some(chain_id) = chain_id {
+        tx.set_chain_id(chain_id);
+    }
+    txs.push(tx);
+    for (i, tx) in txs.iter_mut().enumerate() {
+        tx.set_nonce(nonce + i);
+    }
+    encode_tx_sequence(provider, endpoint, sender, txs).await
+}
+
+/// The fees `fees` resolve to, see [`GasSuggestion::fees`](crate::contracts::gas_oracle::GasSuggestion::fees).
+/// Fee options that can't be met are a 400.
+pub async fn resolve_fees(endpoint: &str, fees: &FeeOptions) -> Result<TxFees, HttpResponse> {
+    let resolved = match GasOracle::new(&config().network().rpc_url) {
+        Ok(oracle) => oracle.fees(fees).await,
+        Err(err) => Err(err),
+    };
+    resolved.map_err(|err| match err.downcast_ref::<InvalidFeeOptions>() {
+        Some(err) => {
+            warn!(endpoint, error = %err, "Rejected fee options");
+            HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string()))
+        },
+        None => {
+            error!(error = ?err, endpoint, "Failed to get gas fees");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to get gas fees"))
+        },
+    })
+}
+
+async fn encode_tx_sequence(provider: &EthProvider, endpoint: &str, sender: Address, txs: Vec<TypedTransaction>) -> HttpResponse {
+    let required = txs.iter().fold(U256::zero(), |acc, tx| {
+        acc + tx.value().copied().unwrap_or_default() + max_fee(tx)
+    });
+    if let Err(err) = check_balance(provider, sender, required).await {
+        return err;
+    }
+
+    let txs = txs
+        .into_iter()
+        .map(|tx| {
+            let tx_hex = EthProvider::create_hex_tx(&tx);
+            UnsignedTx::new(tx, tx_hex)
+        })
+        .collect();
+    metrics::record_tx_built(endpoint);
+    HttpResponse::Ok().json(UnsignedTxSequenceResponse::new(txs))
+}
@@ -0,0 +1,548 @@
+use std::collections::BTreeMap;
+
+use ethers::types::{transaction::{eip2718::TypedTransaction, eip712::TypedData}, Address, H256};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::auth::api_keys::ApiKeyRecord;
+use crate::contracts::token_list::TokenInfo;
+
+/// The `status` field every response body starts with.
+#[derive(Serialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Success,
+    Error,
+}
+
+/// Body of every non-2xx response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    #[schema(example = "error")]
+    pub status: Status,
+    #[schema(example = "Insufficient balance")]
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { status: Status::Error, message: message.into() }
+    }
+}
+
+/// A request turned away by the rate limiter, also sent in the `Retry-After` header.
+#[derive(Serialize, ToSchema)]
+pub struct RateLimitedResponse {
+    #[schema(example = "error")]
+    pub status: Status,
+    #[schema(example = "Rate limit exceeded")]
+    pub message: String,
+    /// Seconds to wait before retrying
+    #[schema(example = 3)]
+    pub retry_after: u64,
+}
+
+impl RateLimitedResponse {
+    pub fn new(message: impl Into<String>, retry_after: u64) -> Self {
+        Self { status: Status::Error, message: message.into(), retry_after }
+    }
+}
+
+/// A failed balance or allowance check. Amounts are in ether for native balances and in token
+/// units for token balances and allowances.
+#[derive(Serialize, ToSchema)]
+pub struct InsufficientFundsResponse {
+    #[schema(example = "error")]
+    pub status: Status,
+    #[schema(example = "Insufficient balance to cover value plus gas")]
+    pub message: String,
+    #[schema(example = "1.000021")]
+    pub required: String,
+    #[schema(example = "1.0")]
+    pub available: String,
+    #[schema(example = "0.000021")]
+    pub shortfall: String,
+}
+
+impl InsufficientFundsResponse {
+    pub fn new(message: impl Into<String>, required: String, available: String, shortfall: String) -> Self {
+        Self { status: Status::Error, message: message.into(), required, available, shortfall }
+    }
+}
+
+/// A transaction ready to be signed by `tx.from`, with gas, fees, nonce and chain id filled in. It
+/// is an EIP-1559 transaction (`type` `0x02`) on networks with a base fee and legacy elsewhere.
+#[derive(Serialize, ToSchema)]
+pub struct UnsignedTxResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = Object, example = json!({
+        "type": "0x02",
+        "from": "0x1111111111111111111111111111111111111111",
+        "to": "0x5555555555555555555555555555555555555555",
+        "gas": "0x5208",
+        "maxFeePerGas": "0x77359401",
+        "maxPriorityFeePerGas": "0x1",
+        "value": "0x14d1120d7b160000",
+        "data": "0x",
+        "nonce": "0x0",
+        "accessList": []
+    }))]
+    pub tx: TypedTransaction,
+    /// What `tx.from` signs: the RLP encoding of `tx` including its chain id, after the type
+    /// byte for EIP-1559 transactions
+    #[schema(example = "0x02ef0180018477359401825208...")]
+    pub tx_hex: String,
+}
+
+impl UnsignedTxResponse {
+    pub fn new(tx: TypedTransaction, tx_hex: String) -> Self {
+        Self { status: Status::Success, tx, tx_hex }
+    }
+}
+
+/// One transaction of an [`UnsignedTxSequenceResponse`].
+#[derive(Serialize, ToSchema)]
+pub struct UnsignedTx {
+    #[schema(value_type = Object)]
+    pub tx: TypedTransaction,
+    /// What `tx.from` signs: the RLP encoding of `tx` including its chain id, after the type
+    /// byte for EIP-1559 transactions
+    pub tx_hex: String,
+}
+
+impl UnsignedTx {
+    pub fn new(tx: TypedTransaction, tx_hex: String) -> Self {
+        Self { tx, tx_hex }
+    }
+}
+
+/// Transactions to sign and broadcast in order, with consecutive nonces.
+#[derive(Serialize, ToSchema)]
+pub struct UnsignedTxSequenceResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    pub txs: Vec<UnsignedTx>,
+}
+
+impl UnsignedTxSequenceResponse {
+    pub fn new(txs: Vec<UnsignedTx>) -> Self {
+        Self { status: Status::Success, txs }
+    }
+}
+
+/// Body of endpoints that return a sequence only for some requests, like `/wallet/disperseToken`
+/// with `wrap`. Only documents the two shapes, handlers send either directly.
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum UnsignedTxOrSequenceResponse {
+    Tx(Box<UnsignedTxResponse>),
+    Sequence(UnsignedTxSequenceResponse),
+}
+
+/// What one recipient of a token disperse is sent and expected to receive.
+#[derive(Serialize, ToSchema)]
+pub struct PreviewTransfer {
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    pub recipient: Address,
+    #[schema(example = "1.0")]
+    pub value: String,
+    #[schema(example = "0.98")]
+    pub expected_received: String,
+}
+
+impl PreviewTransfer {
+    pub fn new(recipient: Address, value: String, expected_received: String) -> Self {
+        Self { recipient, value, expected_received }
+    }
+}
+
+/// Preview of a token disperse, flagging tokens that don't deliver the amount sent.
+#[derive(Serialize, ToSchema)]
+pub struct DisperseTokenPreviewResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "1.5")]
+    pub total: String,
+    /// Whether recipients receive less than sent beyond rounding
+    pub fee_on_transfer: bool,
+    /// Share of each transfer taken as a fee, in basis points
+    #[schema(example = 200)]
+    pub fee_bps: u64,
+    pub transfers: Vec<PreviewTransfer>,
+    #[schema(example = json!(["Fee-on-transfer token: recipients receive about 200 basis points less than sent"]))]
+    pub warnings: Vec<String>,
+}
+
+impl DisperseTokenPreviewResponse {
+    pub fn new(total: String, fee_on_transfer: bool, fee_bps: u64, transfers: Vec<PreviewTransfer>, warnings: Vec<String>) -> Self {
+        Self { status: Status::Success, total, fee_on_transfer, fee_bps, transfers, warnings }
+    }
+}
+
+/// A token balance formatted with the token's decimals.
+#[derive(Serialize, ToSchema)]
+pub struct BalanceResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "250.5")]
+    pub balance: String,
+}
+
+impl BalanceResponse {
+    pub fn new(balance: String) -> Self {
+        Self { status: Status::Success, balance }
+    }
+}
+
+/// Suggested fees of one speed tier, in gwei.
+#[derive(Serialize, ToSchema)]
+pub struct GasFees {
+    /// Most paid per gas, twice the next base fee plus the tip. Without a base fee, the gas price
+    /// of a legacy transaction
+    #[schema(example = "41.5")]
+    pub max_fee_per_gas: String,
+    /// Tip over the base fee. Without a base fee, equal to `max_fee_per_gas`
+    #[schema(example = "1.5")]
+    pub max_priority_fee_per_gas: String,
+}
+
+impl GasFees {
+    pub fn new(max_fee_per_gas: String, max_priority_fee_per_gas: String) -> Self {
+        Self { max_fee_per_gas, max_priority_fee_per_gas }
+    }
+}
+
+/// Fee suggestions for each speed tier.
+#[derive(Serialize, ToSchema)]
+pub struct GasResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    /// Base fee of the next block in gwei, absent on networks without EIP-1559
+    #[schema(example = "20")]
+    pub base_fee: Option<String>,
+    pub slow: GasFees,
+    pub standard: GasFees,
+    pub fast: GasFees,
+}
+
+impl GasResponse {
+    pub fn new(base_fee: Option<String>, slow: GasFees, standard: GasFees, fast: GasFees) -> Self {
+        Self { status: Status::Success, base_fee, slow, standard, fast }
+    }
+}
+
+/// Tokens from the configured token list.
+#[derive(Serialize, ToSchema)]
+pub struct TokenListResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    /// Name of the list, absent when none is configured
+    #[schema(example = "Uniswap Labs Default")]
+    pub name: Option<String>,
+    /// Whether tokens missing from the list are rejected
+    pub strict: bool,
+    pub tokens: Vec<TokenInfo>,
+}
+
+impl TokenListResponse {
+    pub fn new(name: Option<String>, strict: bool, tokens: Vec<TokenInfo>) -> Self {
+        Self { status: Status::Success, name, strict, tokens }
+    }
+}
+
+/// ERC1155 balances, in the order of the request's accounts and ids.
+#[derive(Serialize, ToSchema)]
+pub struct BalancesResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = json!(["10", "0"]))]
+    pub balances: Vec<String>,
+}
+
+impl BalancesResponse {
+    pub fn new(balances: Vec<String>) -> Self {
+        Self { status: Status::Success, balances }
+    }
+}
+
+/// A token allowance formatted with the token's decimals.
+#[derive(Serialize, ToSchema)]
+pub struct AllowanceResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "100.0")]
+    pub allowance: String,
+}
+
+impl AllowanceResponse {
+    pub fn new(allowance: String) -> Self {
+        Self { status: Status::Success, allowance }
+    }
+}
+
+/// Current owner of an NFT.
+#[derive(Serialize, ToSchema)]
+pub struct OwnerResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    pub owner: Address,
+}
+
+impl OwnerResponse {
+    pub fn new(owner: Address) -> Self {
+        Self { status: Status::Success, owner }
+    }
+}
+
+/// Metadata URI of an NFT.
+#[derive(Serialize, ToSchema)]
+pub struct TokenUriResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi/1")]
+    pub token_uri: String,
+}
+
+impl TokenUriResponse {
+    pub fn new(token_uri: String) -> Self {
+        Self { status: Status::Success, token_uri }
+    }
+}
+
+/// Hash of a broadcast transaction, `null` if the node did not return one.
+#[derive(Serialize, ToSchema)]
+pub struct TxHashResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = Option<String>, example = "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060")]
+    pub tx_hash: Option<H256>,
+}
+
+impl TxHashResponse {
+    pub fn new(tx_hash: Option<H256>) -> Self {
+        Self { status: Status::Success, tx_hash }
+    }
+}
+
+/// A signed raw transaction, ready for `/web3/sendSignedTransaction`.
+#[derive(Serialize, ToSchema)]
+pub struct SignedTxResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "0x02f8...")]
+    pub signed_tx: String,
+}
+
+impl SignedTxResponse {
+    pub fn new(signed_tx: String) -> Self {
+        Self { status: Status::Success, signed_tx }
+    }
+}
+
+/// EIP-712 typed data of an EIP-2612 permit, to be signed with `eth_signTypedData_v4`.
+#[derive(Serialize, ToSchema)]
+pub struct PermitTypedDataResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = Object)]
+    pub typed_data: TypedData,
+    /// Pass back with the signature
+    #[schema(example = 1760000000)]
+    pub deadline: u64,
+}
+
+impl PermitTypedDataResponse {
+    pub fn new(typed_data: TypedData, deadline: u64) -> Self {
+        Self { status: Status::Success, typed_data, deadline }
+    }
+}
+
+/// The address that produced a signature, and whether it is the expected one.
+#[derive(Serialize, ToSchema)]
+pub struct RecoveredSignerResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    pub signer: Address,
+    /// Whether `signer` equals the request's `address`, `null` when none was given
+    #[schema(example = true)]
+    pub matches: Option<bool>,
+}
+
+impl RecoveredSignerResponse {
+    pub fn new(signer: Address, expected: Option<Address>) -> Self {
+        Self { status: Status::Success, signer, matches: expected.map(|expected| expected == signer) }
+    }
+}
+
+/// A message signed by the server's keystore signer.
+#[derive(Serialize, ToSchema)]
+pub struct SignedMessageResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    pub signer: Address,
+    /// 65-byte signature, `r || s || v`
+    #[schema(example = "0x5f1c...1b")]
+    pub signature: String,
+}
+
+impl SignedMessageResponse {
+    pub fn new(signer: Address, signature: String) -> Self {
+        Self { status: Status::Success, signer, signature }
+    }
+}
+
+/// A response without data.
+#[derive(Serialize, ToSchema)]
+pub struct SuccessResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+}
+
+impl Default for SuccessResponse {
+    fn default() -> Self {
+        Self { status: Status::Success }
+    }
+}
+
+/// Nonce to put in the next Sign-In with Ethereum message.
+#[derive(Serialize, ToSchema)]
+pub struct NonceResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "8f3a61c0d2b94e7aa1c5f0e9b6d4c2a1")]
+    pub nonce: String,
+}
+
+impl NonceResponse {
+    pub fn new(nonce: String) -> Self {
+        Self { status: Status::Success, nonce }
+    }
+}
+
+/// A Sign-In with Ethereum session, sent back in the `X-Session-Token` header.
+#[derive(Serialize, ToSchema)]
+pub struct SessionResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "siwe_5c1e...")]
+    pub token: String,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    pub address: Address,
+    /// Unix timestamp
+    #[schema(example = 1760086400)]
+    pub expires_at: u64,
+}
+
+impl SessionResponse {
+    pub fn new(token: String, address: Address, expires_at: u64) -> Self {
+        Self { status: Status::Success, token, address, expires_at }
+    }
+}
+
+/// Stored API keys, including revoked ones. Keys themselves are never returned, only their hashes.
+#[derive(Serialize, ToSchema)]
+pub struct KeyListResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(value_type = Vec<Object>)]
+    pub keys: Vec<ApiKeyRecord>,
+}
+
+impl KeyListResponse {
+    pub fn new(keys: Vec<ApiKeyRecord>) -> Self {
+        Self { status: Status::Success, keys }
+    }
+}
+
+/// A newly issued API key, shown only this once.
+#[derive(Serialize, ToSchema)]
+pub struct IssuedKeyResponse {
+    #[schema(example = "success")]
+    pub status: Status,
+    #[schema(example = "wmb_3f9c...")]
+    pub key: String,
+    #[schema(value_type = Object)]
+    pub record: ApiKeyRecord,
+}
+
+impl IssuedKeyResponse {
+    pub fn new(key: String, record: ApiKeyRecord) -> Self {
+        Self { status: Status::Success, key, record }
+    }
+}
+
+/// Outcome of one readiness check, `detail` when it passed and `message` when it failed.
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub detail: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl From<Result<Value, String>> for ReadinessCheck {
+    fn from(result: Result<Value, String>) -> Self {
+        match result {
+            Ok(detail) => Self { ok: true, detail: Some(detail), message: None },
+            Err(message) => Self { ok: false, detail: None, message: Some(message) },
+        }
+    }
+}
+
+/// Every readiness check by name, sent with a 503 and an error `status` when any failed.
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Service is not ready")]
+    pub message: Option<String>,
+    pub checks: BTreeMap<&'static str, ReadinessCheck>,
+}
+
+impl ReadinessResponse {
+    pub fn new(checks: BTreeMap<&'static str, ReadinessCheck>) -> Self {
+        if checks.values().all(|check| check.ok) {
+            Self { status: Status::Success, message: None, checks }
+        } else {
+            Self { status: Status::Error, message: Some("Service is not ready".to_string()), checks }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn readiness_fails_when_any_check_fails() {
+        let passing = ReadinessResponse::new(BTreeMap::from([("rpc", Ok(json!({"block_number": 1})).into())]));
+        assert_eq!(serde_json::to_value(&passing).unwrap(), json!({
+            "status": "success",
+            "checks": {"rpc": {"ok": true, "detail": {"block_number": 1}}},
+        }));
+
+        let failing = ReadinessResponse::new(BTreeMap::from([
+            ("rpc", Ok(json!({"block_number": 1})).into()),
+            ("abi", Err("erc20: failed to load".to_string()).into()),
+        ]));
+        assert_eq!(failing.status, Status::Error);
+        assert_eq!(serde_json::to_value(&failing).unwrap()["checks"]["abi"], json!({"ok": false, "message": "erc20: failed to load"}));
+    }
+
+    #[test]
+    fn rate_limited_body_carries_retry_after() {
+        assert_eq!(serde_json::to_value(RateLimitedResponse::new("Rate limit exceeded", 3)).unwrap(), json!({
+            "status": "error",
+            "message": "Rate limit exceeded",
+            "retry_after": 3,
+        }));
+    }
+}
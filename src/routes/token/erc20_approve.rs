@@ -1,47 +1,178 @@
 use actix_web::{post, web, HttpResponse, Responder};
-use ethers::types::Address;
+use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::erc20::ERC20;
-use crate::contracts::provider::EthProvider;
-use crate::variables::RPC_PROVIDER_URL;
+use crate::contracts::{erc20::ERC20, gas_oracle::FeeOptions, provider::{CallOutputError, EthProvider}};
+use crate::config::config;
+use crate::utils::metrics;
+use crate::routes::checks::{check_balance, max_fee};
+use crate::routes::pipeline::{build_unsigned_erc20_tx, check_erc20_call, prepare_unsigned_tx, resolve_fees};
+use crate::routes::responses::{ErrorResponse, UnsignedTx, UnsignedTxResponse, UnsignedTxSequenceResponse};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct ApproveRequest {
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x3333333333333333333333333333333333333333")]
     spender: Address,
+    #[schema(example = json!("100.0"))]
     amount: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[utoipa::path(
+    post,
+    path = "/token/approve",
+    tag = "token",
+    description = "Build an ERC20 `approve` transaction",
+    request_body = ApproveRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/approve")]
 async fn approve(req: web::Json<ApproveRequest>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
     let amount = match erc20.parse_token(&req.amount).await {
         Ok(amount) => amount,
+        Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
         Err(err) => {
-            eprintln!("Failed to parse amount: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to parse amount" }));
+            error!(error = ?err, "Failed to parse amount");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse amount"));
         },
     };
 
-    let tx = erc20.create_approve_tx(req.sender, req.spender, amount).unwrap();
-    let tx = match erc20.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
+    let tx = erc20.create_approve_tx(req.sender, req.spender, amount);
+    build_unsigned_erc20_tx(&erc20, "/token/approve", req.sender, &req.fees, "approve", tx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/safeApprove",
+    tag = "token",
+    description = "Build the transactions setting the allowance of `spender` to `amount`. Tokens that refuse to change a non-zero allowance (USDT-style) get an `approve(spender, 0)` reset first, so the response holds one or two transactions",
+    request_body = ApproveRequest,
+    responses(
+        (status = 200, description = "Transactions to sign and broadcast in order", body = UnsignedTxSequenceResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/safeApprove")]
+async fn safe_approve(req: web::Json<ApproveRequest>) -> impl Responder {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
+        },
+    };
+
+    let amount = match erc20.parse_token(&req.amount).await {
+        Ok(amount) => amount,
+        Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
         Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
+            error!(error = ?err, "Failed to parse amount");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse amount"));
         },
     };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
 
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let current = match erc20.query_allowance(req.sender, req.spender).await {
+        Ok(current) => current,
+        Err(err) => {
+            error!(error = ?err, "Failed to query allowance");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query allowance"));
+        },
+    };
+    let reset_required = if current.is_zero() || amount.is_zero() || current == amount {
+        false
+    } else {
+        match erc20.needs_allowance_reset(req.sender, req.spender, amount).await {
+            Ok(needs_reset) => needs_reset,
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+            Err(err) => {
+                error!(error = ?err, "Failed to simulate approve");
+                return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate approve"));
+            },
+        }
+    };
+
+    let fees = match resolve_fees("/token/safeApprove", &req.fees).await {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    if !reset_required {
+        let tx = erc20.create_approve_tx(req.sender, req.spender, amount);
+        if let Ok(call) = &tx {
+            if let Err(err) = check_erc20_call(&erc20, "/token/safeApprove", req.sender, "approve", call).await {
+                return err;
+            }
+        }
+        let tx = match prepare_unsigned_tx(&erc20.provider, "/token/safeApprove", req.sender, &fees, tx).await {
+            Ok(tx) => tx,
+            Err(err) => return err,
+        };
+        let tx_hex = EthProvider::create_hex_tx(&tx);
+        metrics::record_tx_built("/token/safeApprove");
+        return HttpResponse::Ok().json(UnsignedTxSequenceResponse::new(vec![UnsignedTx::new(tx, tx_hex)]));
+    }
+
+    let reset = erc20.create_approve_tx(req.sender, req.spender, U256::zero());
+    let reset = match prepare_unsigned_tx(&erc20.provider, "/token/safeApprove", req.sender, &fees, reset).await {
+        Ok(reset) => reset,
+        Err(err) => return err,
+    };
+
+    // the approve reverts until the reset is mined, so it is estimated for a spender without an
+    // allowance, which takes the same zero to non-zero storage write
+    let gas = match erc20.create_approve_tx(req.sender, Address::random(), amount) {
+        Ok(probe) => erc20.provider.estimate_gas(&probe).await,
+        Err(err) => Err(err),
+    };
+    let gas = match gas {
+        Ok(gas) => gas,
+        Err(err) => {
+            error!(error = ?err, "Failed to estimate approve gas");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to estimate approve gas"));
+        },
+    };
+    let approval = match erc20.create_approve_tx(req.sender, req.spender, amount) {
+        Ok(approval) => approval,
+        Err(err) => {
+            error!(error = ?err, "Failed to build transaction");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to build transaction"));
+        },
+    };
+    let mut approval = fees.apply(approval.gas(gas).nonce(reset.nonce().copied().unwrap_or_default() + 1));
+    if let Some(chain_id) = reset.chain_id() {
+        approval.set_chain_id(chain_id);
+    }
+
+    if let Err(err) = check_balance(&erc20.provider, req.sender, max_fee(&reset) + max_fee(&approval)).await {
+        return err;
+    }
+
+    let txs = [reset, approval]
+        .into_iter()
+        .map(|tx| {
+            let tx_hex = EthProvider::create_hex_tx(&tx);
+            UnsignedTx::new(tx, tx_hex)
+        })
+        .collect();
+    metrics::record_tx_built("/token/safeApprove");
+    HttpResponse::Ok().json(UnsignedTxSequenceResponse::new(txs))
 }
@@ -1,23 +1,39 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::types::Address;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::{contracts::erc20::ERC20, variables::RPC_PROVIDER_URL};
+use crate::{config::config, contracts::erc20::ERC20};
+use crate::routes::responses::{BalanceResponse, ErrorResponse};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct BalanceOfRequest {
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     owner: Address,
 }
 
+#[utoipa::path(
+    post,
+    path = "/token/balanceOf",
+    tag = "token",
+    description = "Query the token balance of `owner`",
+    request_body = BalanceOfRequest,
+    responses(
+        (status = 200, description = "Current balance in token units", body = BalanceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/balanceOf")]
 async fn balance_of(req: web::Json<BalanceOfRequest>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
@@ -25,14 +41,14 @@ async fn balance_of(req: web::Json<BalanceOfRequest>) -> impl Responder {
         Ok(res) => match erc20.token_to_string(res).await {
             Ok(res) => res,
             Err(err) => {
-                eprintln!("Failed to convert balance to string: {err:?}");
-                return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to convert balance to string" }));
+                error!(error = ?err, "Failed to convert balance to string");
+                return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to convert balance to string"));
             },
         },
         Err(err) => {
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": err.to_string()}));
+            return HttpResponse::InternalServerError().json(ErrorResponse::new(err.to_string()));
         },
     };
 
-    HttpResponse::Ok().json(json!({"status": "success", "balance": balance }))
+    HttpResponse::Ok().json(BalanceResponse::new(balance))
 }
@@ -0,0 +1,64 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc20::ERC20, gas_oracle::FeeOptions, provider::CallOutputError};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_erc20_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_allowance;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct DecreaseAllowanceRequest {
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x3333333333333333333333333333333333333333")]
+    spender: Address,
+    #[schema(example = json!("100.0"))]
+    amount: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/decreaseAllowance",
+    tag = "token",
+    description = "Build an ERC20 `decreaseAllowance` transaction. The current allowance of `spender` must be at least `amount`",
+    request_body = DecreaseAllowanceRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/decreaseAllowance")]
+async fn decrease_allowance(req: web::Json<DecreaseAllowanceRequest>) -> impl Responder {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
+        },
+    };
+
+    let amount = match erc20.parse_token(&req.amount).await {
+        Ok(amount) => amount,
+        Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+        Err(err) => {
+            error!(error = ?err, "Failed to parse amount");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to parse amount"));
+        },
+    };
+
+    if let Err(err) = check_allowance(&erc20, req.sender, req.spender, amount).await {
+        return err;
+    }
+
+    let tx = erc20.create_decrease_allowance_tx(req.sender, req.spender, amount);
+    build_unsigned_erc20_tx(&erc20, "/token/decreaseAllowance", req.sender, &req.fees, "decreaseAllowance", tx).await
+}
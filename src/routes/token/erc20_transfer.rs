@@ -1,36 +1,57 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::types::Address;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::erc20::ERC20;
-use crate::contracts::provider::EthProvider;
-use crate::variables::RPC_PROVIDER_URL;
-use super::checks::check_token_balance;
+use crate::contracts::{erc20::ERC20, gas_oracle::FeeOptions, provider::CallOutputError};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_erc20_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_token_balance;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct TransferRequest {
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
     recipient: Address,
+    #[schema(example = json!("100.0"))]
     amount: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[utoipa::path(
+    post,
+    path = "/token/transfer",
+    tag = "token",
+    description = "Build an ERC20 `transfer` transaction",
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/transfer")]
 async fn transfer(req: web::Json<TransferRequest>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
     let amount = match erc20.parse_token(&req.amount).await {
         Ok(amount) => amount,
+        Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
         Err(err) => {
-            eprintln!("Failed to parse amount: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to parse amount" }));
+            error!(error = ?err, "Failed to parse amount");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to parse amount"));
         },
     };
 
@@ -38,8 +59,6 @@ async fn transfer(req: web::Json<TransferRequest>) -> impl Responder {
         return err;
     }
 
-    let tx = erc20.create_transfer_tx(req.sender, req.recipient, amount).unwrap();
-    let tx_hex = EthProvider::create_hex_tx(&tx);
-
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let tx = erc20.create_transfer_tx(req.sender, req.recipient, amount);
+    build_unsigned_erc20_tx(&erc20, "/token/transfer", req.sender, &req.fees, "transfer", tx).await
 }
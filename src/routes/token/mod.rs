@@ -1,18 +1,39 @@
 
 use actix_web::{web, Scope};
+use utoipa::OpenApi;
 
 mod erc20_transfer;
 mod erc20_approve;
 mod erc20_transfer_from;
 mod erc20_balance_of;
 mod erc20_allowance;
-mod checks;
+mod erc20_increase_allowance;
+mod erc20_decrease_allowance;
+mod token_list;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    erc20_transfer::transfer,
+    erc20_approve::approve,
+    erc20_approve::safe_approve,
+    erc20_increase_allowance::increase_allowance,
+    erc20_decrease_allowance::decrease_allowance,
+    erc20_transfer_from::transfer_from,
+    erc20_balance_of::balance_of,
+    erc20_allowance::allowance,
+    token_list::token_list,
+))]
+pub struct ApiDoc;
 
 pub fn route() -> Scope {
     web::scope("/token")
         .service(erc20_transfer::transfer)
         .service(erc20_approve::approve)
+        .service(erc20_approve::safe_approve)
+        .service(erc20_increase_allowance::increase_allowance)
+        .service(erc20_decrease_allowance::decrease_allowance)
         .service(erc20_transfer_from::transfer_from)
         .service(erc20_balance_of::balance_of)
         .service(erc20_allowance::allowance)
+        .service(token_list::token_list)
 }
@@ -0,0 +1,36 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::contracts::token_list::TokenRegistry;
+use crate::routes::responses::TokenListResponse;
+
+/// Most tokens returned by one search.
+const MAX_TOKENS: usize = 1000;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct TokenListRequest {
+    /// Address, or part of a symbol or name. Empty lists every token
+    #[serde(default)]
+    #[schema(example = "usd")]
+    query: String,
+    #[schema(example = 20)]
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token/list",
+    tag = "token",
+    description = "Search the configured token list of the network. In strict mode only these tokens are accepted as `token`",
+    request_body = TokenListRequest,
+    responses(
+        (status = 200, description = "Matching tokens, exact symbol matches first", body = TokenListResponse),
+    ),
+)]
+#[post("/list")]
+async fn token_list(req: web::Json<TokenListRequest>, registry: web::Data<TokenRegistry>) -> impl Responder {
+    let limit = req.limit.unwrap_or(MAX_TOKENS).min(MAX_TOKENS);
+    let tokens = registry.search(&req.query, limit).into_iter().cloned().collect();
+    HttpResponse::Ok().json(TokenListResponse::new(registry.name().map(str::to_string), registry.is_strict(), tokens))
+}
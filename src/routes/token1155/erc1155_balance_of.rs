@@ -0,0 +1,105 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::{config::config, contracts::erc1155::ERC1155};
+use crate::utils::web3::parse_integers;
+use crate::routes::responses::{BalanceResponse, BalancesResponse, ErrorResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct BalanceOfRequest {
+    #[schema(value_type = String, example = "0x76BE3b62873462d2142405439777e971754E8E77")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    owner: Address,
+    #[schema(example = "1")]
+    id: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct BalanceOfBatchRequest {
+    #[schema(value_type = String, example = "0x76BE3b62873462d2142405439777e971754E8E77")]
+    token: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x1111111111111111111111111111111111111111", "0x2222222222222222222222222222222222222222"]))]
+    accounts: Vec<Address>,
+    /// `ids[i]` is queried for `accounts[i]`
+    #[schema(example = json!(["1", "1"]))]
+    ids: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token1155/balanceOf",
+    tag = "token1155",
+    description = "Query the balance of `owner` in token `id`",
+    request_body = BalanceOfRequest,
+    responses(
+        (status = 200, description = "Current balance", body = BalanceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/balanceOf")]
+async fn balance_of(req: web::Json<BalanceOfRequest>) -> impl Responder {
+    let erc1155 = match ERC1155::new(&config().network().rpc_url, req.token) {
+        Ok(erc1155) => erc1155,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC1155 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC1155 contract"));
+        },
+    };
+    let Ok(id) = U256::from_dec_str(&req.id) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("id must be an integer"));
+    };
+
+    match erc1155.query_balance_of_batch(vec![req.owner], vec![id]).await {
+        Ok(balances) if balances.len() == 1 => HttpResponse::Ok().json(BalanceResponse::new(balances[0].to_string())),
+        Ok(balances) => {
+            error!(count = balances.len(), "balanceOfBatch returned the wrong number of balances");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"))
+        },
+        Err(err) => {
+            error!(error = ?err, "Failed to query balance");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"))
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/token1155/balanceOfBatch",
+    tag = "token1155",
+    description = "Query the balance of `accounts[i]` in token `ids[i]` for every pair in one call",
+    request_body = BalanceOfBatchRequest,
+    responses(
+        (status = 200, description = "Balances in request order", body = BalancesResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/balanceOfBatch")]
+async fn balance_of_batch(req: web::Json<BalanceOfBatchRequest>) -> impl Responder {
+    if req.accounts.len() != req.ids.len() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("accounts and ids must have the same length"));
+    }
+    let erc1155 = match ERC1155::new(&config().network().rpc_url, req.token) {
+        Ok(erc1155) => erc1155,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC1155 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC1155 contract"));
+        },
+    };
+    let Ok(ids) = parse_integers(&req.ids) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("ids must be integers"));
+    };
+
+    match erc1155.query_balance_of_batch(req.accounts.clone(), ids).await {
+        Ok(balances) => HttpResponse::Ok().json(BalancesResponse::new(balances.iter().map(U256::to_string).collect())),
+        Err(err) => {
+            error!(error = ?err, "Failed to query balances");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balances"))
+        },
+    }
+}
@@ -0,0 +1,68 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc1155::ERC1155, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::utils::web3::parse_integers;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_token1155_balances;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SafeBatchTransferFromRequest {
+    #[schema(value_type = String, example = "0x76BE3b62873462d2142405439777e971754E8E77")]
+    token: Address,
+    /// `from`, or an operator approved by `from`
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    from: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    to: Address,
+    #[schema(example = json!(["1", "2"]))]
+    ids: Vec<String>,
+    /// `amounts[i]` of token `ids[i]`
+    #[schema(example = json!(["10", "1"]))]
+    amounts: Vec<String>,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token1155/safeBatchTransferFrom",
+    tag = "token1155",
+    description = "Build an ERC1155 `safeBatchTransferFrom` transaction moving several token ids to `to` at once",
+    request_body = SafeBatchTransferFromRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/safeBatchTransferFrom")]
+async fn safe_batch_transfer_from(req: web::Json<SafeBatchTransferFromRequest>) -> impl Responder {
+    if req.ids.is_empty() || req.ids.len() != req.amounts.len() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("ids and amounts must be non-empty and of the same length"));
+    }
+    let erc1155 = match ERC1155::new(&config().network().rpc_url, req.token) {
+        Ok(erc1155) => erc1155,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC1155 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC1155 contract"));
+        },
+    };
+    let (Ok(ids), Ok(amounts)) = (parse_integers(&req.ids), parse_integers(&req.amounts)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("ids and amounts must be integers"));
+    };
+
+    if let Err(err) = check_token1155_balances(&erc1155, req.from, &ids, &amounts).await {
+        return err;
+    }
+
+    let tx = erc1155.create_safe_batch_transfer_from_tx(req.sender, req.from, req.to, ids, amounts);
+    build_unsigned_tx(&erc1155.provider, "/token1155/safeBatchTransferFrom", req.sender, &req.fees, tx).await
+}
@@ -0,0 +1,50 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc1155::ERC1155, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SetApprovalForAllRequest {
+    #[schema(value_type = String, example = "0x76BE3b62873462d2142405439777e971754E8E77")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x5555555555555555555555555555555555555555")]
+    operator: Address,
+    /// `false` revokes the operator
+    approved: bool,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token1155/setApprovalForAll",
+    tag = "token1155",
+    description = "Build an ERC1155 `setApprovalForAll` transaction letting `operator` transfer every token of `sender` in the contract",
+    request_body = SetApprovalForAllRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/setApprovalForAll")]
+async fn set_approval_for_all(req: web::Json<SetApprovalForAllRequest>) -> impl Responder {
+    let erc1155 = match ERC1155::new(&config().network().rpc_url, req.token) {
+        Ok(erc1155) => erc1155,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC1155 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC1155 contract"));
+        },
+    };
+
+    let tx = erc1155.create_set_approval_for_all_tx(req.sender, req.operator, req.approved);
+    build_unsigned_tx(&erc1155.provider, "/token1155/setApprovalForAll", req.sender, &req.fees, tx).await
+}
@@ -0,0 +1,63 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc1155::ERC1155, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_token1155_balances;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SafeTransferFromRequest {
+    #[schema(value_type = String, example = "0x76BE3b62873462d2142405439777e971754E8E77")]
+    token: Address,
+    /// `from`, or an operator approved by `from`
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    from: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    to: Address,
+    #[schema(example = "1")]
+    id: String,
+    #[schema(example = "10")]
+    amount: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token1155/safeTransferFrom",
+    tag = "token1155",
+    description = "Build an ERC1155 `safeTransferFrom` transaction moving `amount` of token `id`",
+    request_body = SafeTransferFromRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/safeTransferFrom")]
+async fn safe_transfer_from(req: web::Json<SafeTransferFromRequest>) -> impl Responder {
+    let erc1155 = match ERC1155::new(&config().network().rpc_url, req.token) {
+        Ok(erc1155) => erc1155,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC1155 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC1155 contract"));
+        },
+    };
+    let (Ok(id), Ok(amount)) = (U256::from_dec_str(&req.id), U256::from_dec_str(&req.amount)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("id and amount must be integers"));
+    };
+
+    if let Err(err) = check_token1155_balances(&erc1155, req.from, &[id], &[amount]).await {
+        return err;
+    }
+
+    let tx = erc1155.create_safe_transfer_from_tx(req.sender, req.from, req.to, id, amount);
+    build_unsigned_tx(&erc1155.provider, "/token1155/safeTransferFrom", req.sender, &req.fees, tx).await
+}
@@ -0,0 +1,26 @@
+use actix_web::{web, Scope};
+use utoipa::OpenApi;
+
+mod erc1155_transfer;
+mod erc1155_batch_transfer;
+mod erc1155_set_approval_for_all;
+mod erc1155_balance_of;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    erc1155_transfer::safe_transfer_from,
+    erc1155_batch_transfer::safe_batch_transfer_from,
+    erc1155_set_approval_for_all::set_approval_for_all,
+    erc1155_balance_of::balance_of,
+    erc1155_balance_of::balance_of_batch,
+))]
+pub struct ApiDoc;
+
+pub fn route() -> Scope {
+    web::scope("/token1155")
+        .service(erc1155_transfer::safe_transfer_from)
+        .service(erc1155_batch_transfer::safe_batch_transfer_from)
+        .service(erc1155_set_approval_for_all::set_approval_for_all)
+        .service(erc1155_balance_of::balance_of)
+        .service(erc1155_balance_of::balance_of_batch)
+}
@@ -0,0 +1,47 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::{config::config, contracts::erc721::ERC721};
+use crate::routes::responses::{BalanceResponse, ErrorResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct BalanceOfRequest {
+    #[schema(value_type = String, example = "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    owner: Address,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/balanceOf",
+    tag = "token721",
+    description = "Query how many tokens of the collection `owner` holds",
+    request_body = BalanceOfRequest,
+    responses(
+        (status = 200, description = "Number of tokens", body = BalanceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/balanceOf")]
+async fn balance_of(req: web::Json<BalanceOfRequest>) -> impl Responder {
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+
+    match erc721.query_balance_of(req.owner).await {
+        Ok(balance) => HttpResponse::Ok().json(BalanceResponse::new(balance.to_string())),
+        Err(err) => {
+            error!(error = ?err, "Failed to query balance");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"))
+        },
+    }
+}
@@ -0,0 +1,87 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc721::ERC721, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx_sequence;
+use crate::routes::responses::{ErrorResponse, UnsignedTxSequenceResponse};
+
+/// Most tokens one request may distribute, each one is a transaction to sign.
+const MAX_DISPERSE_TOKENS: usize = 100;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct DisperseRequest {
+    #[schema(value_type = String, example = "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
+    recipients: Vec<Address>,
+    /// `token_ids[i]` goes to `recipients[i]`
+    #[schema(example = json!(["1", "2"]))]
+    token_ids: Vec<String>,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/disperse",
+    tag = "token721",
+    description = "Build one `safeTransferFrom` transaction per token, sending `token_ids[i]` from `sender` to `recipients[i]`",
+    request_body = DisperseRequest,
+    responses(
+        (status = 200, description = "Transactions to sign and broadcast in order", body = UnsignedTxSequenceResponse),
+        (status = 400, description = "Invalid request, a token not owned by `sender`, or a transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/disperse")]
+async fn disperse(req: web::Json<DisperseRequest>) -> impl Responder {
+    if req.recipients.len() != req.token_ids.len() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("recipients and token_ids must have the same length"));
+    }
+    if req.token_ids.is_empty() || req.token_ids.len() > MAX_DISPERSE_TOKENS {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Between 1 and {MAX_DISPERSE_TOKENS} tokens can be dispersed at once")));
+    }
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+
+    let mut token_ids: Vec<U256> = vec![];
+    for token_id in req.token_ids.iter() {
+        match U256::from_dec_str(token_id) {
+            Ok(token_id) if !token_ids.contains(&token_id) => token_ids.push(token_id),
+            Ok(_) => return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Token {token_id} is listed more than once"))),
+            Err(_) => return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Invalid token id {token_id:?}"))),
+        }
+    }
+
+    // issued concurrently so the transport sends them as one batch
+    let owners = join_all(token_ids.iter().map(|token_id| erc721.query_owner_of(*token_id))).await;
+    for (token_id, owner) in token_ids.iter().zip(owners) {
+        match owner {
+            Ok(owner) if owner == req.sender => {},
+            Ok(_) => return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Token {token_id} is not owned by sender"))),
+            Err(err) => {
+                error!(error = ?err, "Failed to query owner");
+                return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Failed to query the owner of token {token_id}, it may not exist")));
+            },
+        }
+    }
+
+    let txs = req.recipients
+        .iter()
+        .zip(token_ids)
+        .map(|(recipient, token_id)| erc721.create_safe_transfer_from_tx(req.sender, req.sender, *recipient, token_id))
+        .collect();
+    build_unsigned_tx_sequence(&erc721.provider, "/token721/disperse", req.sender, &req.fees, txs).await
+}
@@ -0,0 +1,50 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::{config::config, contracts::erc721::ERC721};
+use crate::routes::responses::{ErrorResponse, OwnerResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct OwnerOfRequest {
+    #[schema(value_type = String, example = "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D")]
+    token: Address,
+    #[schema(example = "1")]
+    token_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/ownerOf",
+    tag = "token721",
+    description = "Query the owner of an NFT",
+    request_body = OwnerOfRequest,
+    responses(
+        (status = 200, description = "Current owner", body = OwnerResponse),
+        (status = 400, description = "Invalid request or the token does not exist", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/ownerOf")]
+async fn owner_of(req: web::Json<OwnerOfRequest>) -> impl Responder {
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+    let Ok(token_id) = U256::from_dec_str(&req.token_id) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid token id"));
+    };
+
+    match erc721.query_owner_of(token_id).await {
+        Ok(owner) => HttpResponse::Ok().json(OwnerResponse::new(owner)),
+        Err(err) => {
+            error!(error = ?err, "Failed to query owner");
+            HttpResponse::BadRequest().json(ErrorResponse::new("Failed to query owner, the token may not exist"))
+        },
+    }
+}
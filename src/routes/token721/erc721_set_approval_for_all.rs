@@ -0,0 +1,50 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc721::ERC721, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SetApprovalForAllRequest {
+    #[schema(value_type = String, example = "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x5555555555555555555555555555555555555555")]
+    operator: Address,
+    /// `false` revokes the operator
+    approved: bool,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/setApprovalForAll",
+    tag = "token721",
+    description = "Build an ERC721 `setApprovalForAll` transaction letting `operator` transfer every token of `sender` in the collection",
+    request_body = SetApprovalForAllRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/setApprovalForAll")]
+async fn set_approval_for_all(req: web::Json<SetApprovalForAllRequest>) -> impl Responder {
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+
+    let tx = erc721.create_set_approval_for_all_tx(req.sender, req.operator, req.approved);
+    build_unsigned_tx(&erc721.provider, "/token721/setApprovalForAll", req.sender, &req.fees, tx).await
+}
@@ -0,0 +1,85 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{erc721::ERC721, gas_oracle::FeeOptions};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct TransferFromRequest {
+    #[schema(value_type = String, example = "0xBC4CA0EdA7647A8aB7C2061c2E118A18a936f13D")]
+    token: Address,
+    /// The owner, or an address approved for the token
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    from: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    to: Address,
+    #[schema(example = "1")]
+    token_id: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/transferFrom",
+    tag = "token721",
+    description = "Build an ERC721 `transferFrom` transaction. Tokens sent to a contract that can't handle them are lost, prefer `/token721/safeTransferFrom`",
+    request_body = TransferFromRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/transferFrom")]
+async fn transfer_from(req: web::Json<TransferFromRequest>) -> impl Responder {
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+    let Ok(token_id) = U256::from_dec_str(&req.token_id) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid token id"));
+    };
+
+    let tx = erc721.create_transfer_from_tx(req.sender, req.from, req.to, token_id);
+    build_unsigned_tx(&erc721.provider, "/token721/transferFrom", req.sender, &req.fees, tx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/token721/safeTransferFrom",
+    tag = "token721",
+    description = "Build an ERC721 `safeTransferFrom` transaction, which reverts when `to` is a contract that does not accept ERC721 tokens",
+    request_body = TransferFromRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/safeTransferFrom")]
+async fn safe_transfer_from(req: web::Json<TransferFromRequest>) -> impl Responder {
+    let erc721 = match ERC721::new(&config().network().rpc_url, req.token) {
+        Ok(erc721) => erc721,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC721 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC721 contract"));
+        },
+    };
+    let Ok(token_id) = U256::from_dec_str(&req.token_id) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Invalid token id"));
+    };
+
+    let tx = erc721.create_safe_transfer_from_tx(req.sender, req.from, req.to, token_id);
+    build_unsigned_tx(&erc721.provider, "/token721/safeTransferFrom", req.sender, &req.fees, tx).await
+}
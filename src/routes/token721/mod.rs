@@ -0,0 +1,32 @@
+use actix_web::{web, Scope};
+use utoipa::OpenApi;
+
+mod erc721_transfer;
+mod erc721_set_approval_for_all;
+mod erc721_disperse;
+mod erc721_owner_of;
+mod erc721_balance_of;
+mod erc721_token_uri;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    erc721_transfer::transfer_from,
+    erc721_transfer::safe_transfer_from,
+    erc721_set_approval_for_all::set_approval_for_all,
+    erc721_disperse::disperse,
+    erc721_owner_of::owner_of,
+    erc721_balance_of::balance_of,
+    erc721_token_uri::token_uri,
+))]
+pub struct ApiDoc;
+
+pub fn route() -> Scope {
+    web::scope("/token721")
+        .service(erc721_transfer::transfer_from)
+        .service(erc721_transfer::safe_transfer_from)
+        .service(erc721_set_approval_for_all::set_approval_for_all)
+        .service(erc721_disperse::disperse)
+        .service(erc721_owner_of::owner_of)
+        .service(erc721_balance_of::balance_of)
+        .service(erc721_token_uri::token_uri)
+}
@@ -1,27 +1,46 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::types::Address;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::disperse_collect::DisperseCollect;
-use crate::contracts::provider::EthProvider;
+use crate::contracts::{disperse_collect::DisperseCollect, gas_oracle::FeeOptions};
 use crate::utils::web3::parse_ether;
-use super::checks::check_balance;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_balance;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct CollectEtherRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
     recipient: Address,
+    #[schema(example = json!("1.5"))]
     value: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[utoipa::path(
+    post,
+    path = "/wallet/collectEther",
+    tag = "wallet",
+    description = "Build a transaction collecting ether from `sender` into `recipient`",
+    request_body = CollectEtherRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/collectEther")]
 async fn collect_ether(req: web::Json<CollectEtherRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
     let value = match parse_ether(&req.value) {
         Ok(value) => value,
         Err(err) => {
-            eprintln!("Failed to parse ether: {err:?}");
-            return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to parse ether" }));
+            error!(error = ?err, "Failed to parse ether");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
         },
     };
 
@@ -29,15 +48,6 @@ async fn collect_ether(req: web::Json<CollectEtherRequest>, disperse_collect: we
         return err;
     }
 
-    let tx = disperse_collect.create_collect_ether_tx(req.sender, req.recipient, value).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
-        Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
-        },
-    };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
-
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let tx = disperse_collect.create_collect_ether_tx(req.sender, req.recipient, value);
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/collectEther", req.sender, &req.fees, tx).await
 }
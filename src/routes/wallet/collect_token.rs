@@ -1,29 +1,69 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::{disperse_collect::DisperseCollect, erc20::ERC20};
-use crate::contracts::provider::EthProvider;
-use crate::variables::RPC_PROVIDER_URL;
-use super::checks::{check_token_balance, check_allowance};
+use crate::contracts::{disperse_collect::DisperseCollect, erc20::ERC20, gas_oracle::FeeOptions, provider::CallOutputError};
+use crate::config::config;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse, UnsignedTxSequenceResponse};
+use crate::routes::checks::{check_token_balance, check_allowance};
+use super::permit::{build_with_permits, PermitSignature};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct CollectTokenRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
     recipient: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
     contributors: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
     values: Vec<String>,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[derive(Deserialize, Serialize, ToSchema)]
+struct CollectTokenWithPermitRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    token: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    recipient: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
+    contributors: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
+    values: Vec<String>,
+    /// Permits of contributors without a sufficient allowance
+    permits: Vec<PermitSignature>,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/collectToken",
+    tag = "wallet",
+    description = "Build a transaction collecting tokens from each contributor into `recipient`",
+    request_body = CollectTokenRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/collectToken")]
 async fn collect_token(req: web::Json<CollectTokenRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
@@ -31,9 +71,10 @@ async fn collect_token(req: web::Json<CollectTokenRequest>, disperse_collect: we
     for value in req.values.iter() {
         match erc20.parse_token(value).await {
             Ok(value) => values.push(value),
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
             Err(err) => {
-                eprintln!("Failed to parse token: {err:?}");
-                return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to parse token" }));
+                error!(error = ?err, "Failed to parse token");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse token"));
             },
         }
     }
@@ -47,15 +88,54 @@ async fn collect_token(req: web::Json<CollectTokenRequest>, disperse_collect: we
         }
     }
 
-    let tx = disperse_collect.create_collect_token_tx(req.sender, req.token, req.recipient, req.contributors.to_owned(), values).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
+    let tx = disperse_collect.create_collect_token_tx(req.sender, req.token, req.recipient, req.contributors.to_owned(), values);
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/collectToken", req.sender, &req.fees, tx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/collectTokenWithPermit",
+    tag = "wallet",
+    description = "Like `/wallet/collectToken`, but contributors may grant their allowance with an EIP-2612 permit (see `/wallet/permit`) instead of an approve. Returns one `permit` transaction per permit followed by the collect, all sent by `sender`",
+    request_body = CollectTokenWithPermitRequest,
+    responses(
+        (status = 200, description = "Transactions to sign and broadcast in order", body = UnsignedTxSequenceResponse),
+        (status = 400, description = "Invalid request or permit, or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/collectTokenWithPermit")]
+async fn collect_token_with_permit(req: web::Json<CollectTokenWithPermitRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    if let Some(permit) = req.permits.iter().find(|permit| !req.contributors.contains(&permit.owner)) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Permit of {:?} does not belong to a contributor", permit.owner)));
+    }
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
 
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let mut values: Vec<U256> = vec![];
+    for value in req.values.iter() {
+        match erc20.parse_token(value).await {
+            Ok(value) => values.push(value),
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+            Err(err) => {
+                error!(error = ?err, "Failed to parse token");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse token"));
+            },
+        }
+    }
+
+    for (contributor, value) in req.contributors.iter().zip(values.iter()) {
+        if let Err(err) = check_token_balance(&erc20, *contributor, *value).await {
+            return err;
+        }
+    }
+
+    let required: Vec<(Address, U256)> = req.contributors.iter().copied().zip(values.iter().copied()).collect();
+    let tx = disperse_collect.create_collect_token_tx(req.sender, req.token, req.recipient, req.contributors.to_owned(), values);
+    build_with_permits(&erc20, &disperse_collect, "/wallet/collectTokenWithPermit", req.sender, &req.fees, &req.permits, &required, tx).await
 }
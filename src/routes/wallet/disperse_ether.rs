@@ -1,29 +1,55 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::disperse_collect::DisperseCollect;
-use crate::contracts::provider::EthProvider;
+use crate::contracts::{disperse_collect::DisperseCollect, gas_oracle::FeeOptions};
 use crate::utils::web3::parse_ether;
-use super::checks::check_balance;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_balance;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct DisperseEtherRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
     recipients: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
     values: Vec<String>,
+    #[schema(example = json!("1.5"))]
     value: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct DisperseEtherByPercentRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
     recipients: Vec<Address>,
+    #[schema(example = json!([60, 40]))]
     percentages: Vec<u8>,
+    #[schema(example = json!("1.5"))]
     value: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[utoipa::path(
+    post,
+    path = "/wallet/disperseEther",
+    tag = "wallet",
+    description = "Build a transaction sending ether from `sender` to each recipient in the given amounts",
+    request_body = DisperseEtherRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/disperseEther")]
 async fn disperse_ether(req: web::Json<DisperseEtherRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
     let mut values: Vec<U256> = vec![];
@@ -31,16 +57,16 @@ async fn disperse_ether(req: web::Json<DisperseEtherRequest>, disperse_collect:
         match parse_ether(value) {
             Ok(value) => values.push(value),
             Err(err) => {
-                eprintln!("Failed to parse ether: {err:?}");
-                return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to parse ether" }));
+                error!(error = ?err, "Failed to parse ether");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
             },
         }
     }
     let value = match parse_ether(&req.value) {
         Ok(value) => value,
         Err(err) => {
-            eprintln!("Failed to parse ether: {err:?}");
-            return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to parse ether" }));
+            error!(error = ?err, "Failed to parse ether");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
         },
     };
 
@@ -48,27 +74,30 @@ async fn disperse_ether(req: web::Json<DisperseEtherRequest>, disperse_collect:
         return err;
     }
 
-    let tx = disperse_collect.create_disperse_ether_tx(req.sender, req.recipients.to_owned(), values, value).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
-        Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
-        },
-    };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
-
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let tx = disperse_collect.create_disperse_ether_tx(req.sender, req.recipients.to_owned(), values, value);
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/disperseEther", req.sender, &req.fees, tx).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/wallet/disperseEtherByPercent",
+    tag = "wallet",
+    description = "Build a transaction splitting `value` ether between recipients by percentage",
+    request_body = DisperseEtherByPercentRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/disperseEtherByPercent")]
 async fn disperse_ether_by_percent(req: web::Json<DisperseEtherByPercentRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
     let percentages: Vec<U256> = req.percentages.iter().map(|&p| U256::from(p)).collect();
     let value = match parse_ether(&req.value) {
         Ok(value) => value,
         Err(err) => {
-            eprintln!("Failed to parse ether: {err:?}");
-            return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to parse ether" }));
+            error!(error = ?err, "Failed to parse ether");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
         },
     };
 
@@ -76,15 +105,6 @@ async fn disperse_ether_by_percent(req: web::Json<DisperseEtherByPercentRequest>
         return err;
     }
 
-    let tx = disperse_collect.create_disperse_ether_by_percent_tx(req.sender, req.recipients.to_owned(), percentages, value).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
-        Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
-        },
-    };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
-
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let tx = disperse_collect.create_disperse_ether_by_percent_tx(req.sender, req.recipients.to_owned(), percentages, value);
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/disperseEtherByPercent", req.sender, &req.fees, tx).await
 }
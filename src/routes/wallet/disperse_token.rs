@@ -1,36 +1,83 @@
 use actix_web::{post, web, HttpResponse, Responder};
-use ethers::types::{Address, U256};
+use ethers::types::{Address, TransactionRequest, U256};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
-use crate::contracts::{disperse_collect::DisperseCollect, erc20::ERC20};
-use crate::contracts::provider::EthProvider;
-use crate::variables::RPC_PROVIDER_URL;
-use super::checks::{check_token_balance, check_allowance};
+use crate::contracts::{disperse_collect::DisperseCollect, erc20::ERC20, gas_oracle::FeeOptions, provider::CallOutputError};
+use crate::config::config;
+use crate::routes::pipeline::{build_unsigned_tx, build_unsigned_tx_after, build_unsigned_tx_sequence, prepare_unsigned_tx, resolve_fees};
+use crate::routes::weth::network_weth;
+use crate::routes::responses::{ErrorResponse, UnsignedTxOrSequenceResponse, UnsignedTxResponse, UnsignedTxSequenceResponse};
+use crate::routes::checks::{check_balance, check_token_balance, check_allowance};
+use super::permit::{build_with_permits, PermitSignature};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct DisperseTokenRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
     recipients: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
     values: Vec<String>,
+    /// Wrap the ether missing from the sender's balance first, `token` must be the network's WETH
+    #[serde(default)]
+    wrap: bool,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct DisperseTokenByPercentRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
     sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
     token: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
     recipients: Vec<Address>,
+    #[schema(example = json!([60, 40]))]
     percentages: Vec<u8>,
+    #[serde(flatten)]
+    fees: FeeOptions,
 }
 
+#[derive(Deserialize, Serialize, ToSchema)]
+struct DisperseTokenWithPermitRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    token: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
+    recipients: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
+    values: Vec<String>,
+    /// Permit signed by `sender` for at least the sum of `values`
+    permit: PermitSignature,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/disperseToken",
+    tag = "wallet",
+    description = "Build a transaction sending tokens from `sender` to each recipient in the given amounts. With `wrap` the response is an `UnsignedTxSequenceResponse` whose WETH `deposit`, when the balance falls short, comes before the disperse",
+    request_body = DisperseTokenRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding, or with `wrap` the transactions to sign and broadcast in order", body = UnsignedTxOrSequenceResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/disperseToken")]
 async fn disperse_token(req: web::Json<DisperseTokenRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
@@ -38,42 +85,110 @@ async fn disperse_token(req: web::Json<DisperseTokenRequest>, disperse_collect:
     for value in req.values.iter() {
         match erc20.parse_token(value).await {
             Ok(value) => values.push(value),
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
             Err(err) => {
-                eprintln!("Failed to parse token: {err:?}");
-                return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to parse token" }));
+                error!(error = ?err, "Failed to parse token");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse token"));
             },
         }
     }
-    let total_value: U256 = values.iter().cloned().fold(U256::zero(), |acc, v| acc + v);
+    let Some(total_value) = values.iter().try_fold(U256::zero(), |acc, v| acc.checked_add(*v)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("values overflow"));
+    };
 
-    if let Err(err) = check_token_balance(&erc20, req.sender, total_value).await {
-        return err;
+    if !req.wrap {
+        if let Err(err) = check_token_balance(&erc20, req.sender, total_value).await {
+            return err;
+        }
     }
 
     if let Err(err) = check_allowance(&erc20, req.sender, disperse_collect.contract.address(), total_value).await {
         return err;
     }
 
-    let tx = disperse_collect.create_disperse_token_tx(req.sender, req.token, req.recipients.to_owned(), values).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
+    let tx = disperse_collect.create_disperse_token_tx(req.sender, req.token, req.recipients.to_owned(), values);
+    if req.wrap {
+        return wrap_then_build(&erc20, &disperse_collect, "/wallet/disperseToken", req.sender, &req.fees, total_value, tx).await;
+    }
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/disperseToken", req.sender, &req.fees, tx).await
+}
+
+/// Builds a WETH `deposit` of whatever `sender` lacks of `required` followed by `tx`, which spends
+/// `required` WETH. `tx` is simulated with the balance the deposit leaves behind.
+async fn wrap_then_build(
+    erc20: &ERC20,
+    disperse_collect: &DisperseCollect,
+    endpoint: &str,
+    sender: Address,
+    fees: &FeeOptions,
+    required: U256,
+    tx: Result<TransactionRequest, Box<dyn std::error::Error>>,
+) -> HttpResponse {
+    if config().network().weth_address() != Some(erc20.contract.address()) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("wrap requires token to be the network's WETH"));
+    }
+    let weth = match network_weth() {
+        Ok(weth) => weth,
+        Err(err) => {
+            error!(error = ?err, "Failed to create WETH contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create WETH contract"));
+        },
+    };
+    let balance = match weth.query_balance_of(sender).await {
+        Ok(balance) => balance,
         Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
+            error!(error = ?err, "Failed to query WETH balance");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query WETH balance"));
         },
     };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
+    if balance >= required {
+        return build_unsigned_tx_sequence(&disperse_collect.provider, endpoint, sender, fees, vec![tx]).await;
+    }
+    // the deposit's gas is checked once it is prepared, this reports a plain shortfall of ether
+    if let Err(err) = check_balance(&weth.provider, sender, required - balance).await {
+        return err;
+    }
 
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let fees = match resolve_fees(endpoint, fees).await {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    let deposit = match prepare_unsigned_tx(&weth.provider, endpoint, sender, &fees, weth.create_deposit_tx(sender, required - balance)).await {
+        Ok(deposit) => deposit,
+        Err(err) => return err,
+    };
+    let state = match erc20.balance_override(&[(sender, required)]).await {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Cannot simulate the token after the deposit"));
+        },
+        Err(err) => {
+            error!(error = ?err, "Failed to locate balance storage");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate transaction, the node may not support state overrides"));
+        },
+    };
+    build_unsigned_tx_after(&disperse_collect.provider, endpoint, sender, &fees, vec![deposit], tx, &state).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/wallet/disperseTokenByPercent",
+    tag = "wallet",
+    description = "Build a transaction splitting the sender's token allowance between recipients by percentage",
+    request_body = DisperseTokenByPercentRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/disperseTokenByPercent")]
 async fn disperse_token_by_percent(req: web::Json<DisperseTokenByPercentRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
-    let erc20 = match ERC20::new(&RPC_PROVIDER_URL, req.token) {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
         Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to create ERC20 contract: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create ERC20 contract" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
 
@@ -82,32 +197,72 @@ async fn disperse_token_by_percent(req: web::Json<DisperseTokenByPercentRequest>
     let value = match erc20.query_allowance(req.sender, disperse_collect.contract.address()).await {
         Ok(value) => value,
         Err(err) => {
-            eprintln!("Failed to query allowance: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to query allowance" }));
+            error!(error = ?err, "Failed to query allowance");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query allowance"));
         },
     };
 
     if value == U256::zero() {
-        return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Allowance is zero" }));
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Allowance is zero"));
     }
 
     if total_percentage > U256::from(100) {
-        return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Total percentage exceeds 100"}));
+        return HttpResponse::BadRequest().json(ErrorResponse::new("Total percentage exceeds 100"));
     }
 
     if let Err(err) = check_token_balance(&erc20, req.sender, value).await {
         return err;
     }
 
-    let tx = disperse_collect.create_disperse_token_by_percent_tx(req.sender, req.token, req.recipients.to_owned(), percentages).unwrap();
-    let tx = match disperse_collect.provider.prepare_tx(tx, req.sender).await {
-        Ok(tx) => tx,
+    let tx = disperse_collect.create_disperse_token_by_percent_tx(req.sender, req.token, req.recipients.to_owned(), percentages);
+    build_unsigned_tx(&disperse_collect.provider, "/wallet/disperseTokenByPercent", req.sender, &req.fees, tx).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/disperseTokenWithPermit",
+    tag = "wallet",
+    description = "Like `/wallet/disperseToken`, but the allowance comes from an EIP-2612 permit signed by `sender` (see `/wallet/permit`) instead of an approve. Returns the `permit` transaction followed by the disperse",
+    request_body = DisperseTokenWithPermitRequest,
+    responses(
+        (status = 200, description = "Transactions to sign and broadcast in order", body = UnsignedTxSequenceResponse),
+        (status = 400, description = "Invalid request or permit, or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/disperseTokenWithPermit")]
+async fn disperse_token_with_permit(req: web::Json<DisperseTokenWithPermitRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    if req.permit.owner != req.sender {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("The permit must be signed by sender"));
+    }
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
         Err(err) => {
-            eprintln!("Failed to prepare transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to prepare transaction" }));
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
         },
     };
-    let tx_hex = EthProvider::create_hex_tx(&tx);
 
-    HttpResponse::Ok().json(json!({"status": "success", "tx": tx, "tx_hex": tx_hex }))
+    let mut values: Vec<U256> = vec![];
+    for value in req.values.iter() {
+        match erc20.parse_token(value).await {
+            Ok(value) => values.push(value),
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+            Err(err) => {
+                error!(error = ?err, "Failed to parse token");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse token"));
+            },
+        }
+    }
+    let Some(total_value) = values.iter().try_fold(U256::zero(), |acc, v| acc.checked_add(*v)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("values overflow"));
+    };
+
+    if let Err(err) = check_token_balance(&erc20, req.sender, total_value).await {
+        return err;
+    }
+
+    let tx = disperse_collect.create_disperse_token_tx(req.sender, req.token, req.recipients.to_owned(), values);
+    let permits = std::slice::from_ref(&req.permit);
+    build_with_permits(&erc20, &disperse_collect, "/wallet/disperseTokenWithPermit", req.sender, &req.fees, permits, &[(req.sender, total_value)], tx).await
 }
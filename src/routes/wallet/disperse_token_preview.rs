@@ -0,0 +1,125 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, U256, U512};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use utoipa::ToSchema;
+
+use crate::contracts::{erc20::ERC20, provider::{CallOutputError, EthProvider}, transfer_probe::simulate_transfer};
+use crate::config::config;
+use crate::routes::responses::{DisperseTokenPreviewResponse, ErrorResponse, PreviewTransfer};
+
+/// Losses up to this many base units are reported as rounding rather than a transfer fee.
+/// Share-based tokens such as stETH round each transfer down by a wei or two.
+const ROUNDING_TOLERANCE: u64 = 2;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct DisperseTokenPreviewRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    token: Address,
+    #[schema(value_type = Vec<String>, example = json!(["0x2222222222222222222222222222222222222222", "0x3333333333333333333333333333333333333333"]))]
+    recipients: Vec<Address>,
+    #[schema(example = json!(["0.5", "1.0"]))]
+    values: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/previewDisperseToken",
+    tag = "wallet",
+    description = "Preview `/wallet/disperseToken`: simulates a transfer from `sender` to the first recipient with `eth_call` state overrides and estimates what every recipient receives. Fee-on-transfer and rounding tokens are flagged with warnings",
+    request_body = DisperseTokenPreviewRequest,
+    responses(
+        (status = 200, description = "Expected amounts and warnings", body = DisperseTokenPreviewResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/previewDisperseToken")]
+async fn preview_disperse_token(req: web::Json<DisperseTokenPreviewRequest>) -> impl Responder {
+    if req.recipients.is_empty() || req.recipients.len() != req.values.len() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("recipients and values must be non-empty and have the same length"));
+    }
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
+        },
+    };
+
+    let mut values: Vec<U256> = vec![];
+    for value in req.values.iter() {
+        match erc20.parse_token(value).await {
+            Ok(value) => values.push(value),
+            Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+            Err(err) => {
+                error!(error = ?err, "Failed to parse token");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse token"));
+            },
+        }
+    }
+    let Some(total_value) = values.iter().try_fold(U256::zero(), |acc, v| acc.checked_add(*v)) else {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("values overflow"));
+    };
+
+    let mut warnings: Vec<String> = vec![];
+    let mut fee_bps = U256::zero();
+    // recipients are assumed to lose the same share of their value as the simulated transfer
+    let mut received_share = (U256::one(), U256::one());
+    match simulate_transfer(&erc20, req.sender, req.recipients[0], values[0]).await {
+        Ok(Some(simulation)) if simulation.is_exact() => {},
+        Ok(Some(simulation)) => {
+            let (amount, received, spent) = (simulation.amount, simulation.received, simulation.spent);
+            if received.is_zero() {
+                warnings.push("The recipient's balance did not change, transfers of this token fail without reverting".to_string());
+                received_share = (U256::zero(), U256::one());
+            } else if received > amount {
+                warnings.push("The recipient gained more than was sent, the token may rebase".to_string());
+            } else if amount - received <= U256::from(ROUNDING_TOLERANCE) {
+                warnings.push(format!("Transfers round down by {} base units, as share-based (rebasing) tokens do", amount - received));
+            } else {
+                fee_bps = simulation.fee_bps();
+                warnings.push(format!("Fee-on-transfer token: recipients receive about {fee_bps} basis points less than sent"));
+                received_share = (received, amount);
+            }
+            if spent > amount {
+                warnings.push(format!("The sender is charged {} base units on top of a transfer of {amount}, the balance check may fall short", spent - amount));
+            }
+        },
+        Ok(None) => warnings.push("The sender's balance is too low and the token's balance storage can't be overridden, transfer fees were not checked".to_string()),
+        Err(err) => match EthProvider::rpc_error_message(err.as_ref()) {
+            Some(reason) => {
+                warn!(token = ?req.token, reason = %reason, "Simulated transfer reverted");
+                warnings.push(format!("A transfer to the first recipient would fail: {reason}"));
+            },
+            None => {
+                error!(error = ?err, "Failed to simulate transfer");
+                return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate transfer, the node may not support state overrides"));
+            },
+        },
+    }
+
+    let mut transfers: Vec<PreviewTransfer> = vec![];
+    for (recipient, value) in req.recipients.iter().zip(values) {
+        // the share is at most one, so the expected amount fits whenever `value` does
+        let expected = U256::try_from(value.full_mul(received_share.0) / U512::from(received_share.1)).unwrap_or(value);
+        match tokio::try_join!(erc20.token_to_string(value), erc20.token_to_string(expected)) {
+            Ok((value, expected)) => transfers.push(PreviewTransfer::new(*recipient, value, expected)),
+            Err(err) => {
+                error!(error = ?err, "Failed to convert amount to string");
+                return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to convert amount to string"));
+            },
+        }
+    }
+    let total = match erc20.token_to_string(total_value).await {
+        Ok(total) => total,
+        Err(err) => {
+            error!(error = ?err, "Failed to convert amount to string");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to convert amount to string"));
+        },
+    };
+
+    HttpResponse::Ok().json(DisperseTokenPreviewResponse::new(total, !fee_bps.is_zero(), fee_bps.as_u64(), transfers, warnings))
+}
@@ -1,10 +1,29 @@
 use actix_web::{web, Scope};
+use utoipa::OpenApi;
 
 mod disperse_ether;
 mod disperse_token;
+mod disperse_token_preview;
 mod collect_ether;
 mod collect_token;
-mod checks;
+mod send_ether;
+mod permit;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    disperse_ether::disperse_ether,
+    disperse_ether::disperse_ether_by_percent,
+    disperse_token::disperse_token,
+    disperse_token::disperse_token_by_percent,
+    disperse_token_preview::preview_disperse_token,
+    collect_ether::collect_ether,
+    collect_token::collect_token,
+    send_ether::send_ether,
+    permit::permit,
+    disperse_token::disperse_token_with_permit,
+    collect_token::collect_token_with_permit,
+))]
+pub struct ApiDoc;
 
 pub fn route() -> Scope {
     web::scope("/wallet")
@@ -12,6 +31,11 @@ pub fn route() -> Scope {
         .service(disperse_ether::disperse_ether_by_percent)
         .service(disperse_token::disperse_token)
         .service(disperse_token::disperse_token_by_percent)
+        .service(disperse_token_preview::preview_disperse_token)
         .service(collect_ether::collect_ether)
         .service(collect_token::collect_token)
+        .service(send_ether::send_ether)
+        .service(permit::permit)
+        .service(disperse_token::disperse_token_with_permit)
+        .service(collect_token::collect_token_with_permit)
 }
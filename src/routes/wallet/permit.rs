@@ -0,0 +1,227 @@
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::{Address, Signature, TransactionRequest, U256};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use utoipa::ToSchema;
+
+use crate::contracts::{disperse_collect::DisperseCollect, erc20::{permit_v, ERC20}, gas_oracle::FeeOptions, provider::{CallOutputError, EthProvider}};
+use crate::config::config;
+use crate::routes::checks::check_allowance;
+use crate::routes::pipeline::{build_unsigned_tx_after, prepare_unsigned_tx, resolve_fees};
+use crate::routes::responses::{ErrorResponse, PermitTypedDataResponse};
+
+/// Default validity of a permit when the request sets no deadline.
+const DEFAULT_PERMIT_TTL_SECS: u64 = 3600;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct PermitRequest {
+    #[schema(value_type = String, example = "0x6B175474E89094C44Da98b954EedeAC495271d0F")]
+    token: Address,
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    owner: Address,
+    /// Allowance granted to DisperseCollect, in token units
+    #[schema(example = json!("100.0"))]
+    amount: String,
+    /// Unix timestamp after which the permit is invalid, defaults to one hour from now
+    #[serde(default)]
+    deadline: Option<u64>,
+}
+
+/// An EIP-2612 permit signed by `owner` over the typed data from `/wallet/permit`.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub(super) struct PermitSignature {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    pub owner: Address,
+    /// The signed allowance, in token units
+    #[schema(example = json!("100.0"))]
+    pub amount: String,
+    #[schema(example = 1760000000)]
+    pub deadline: u64,
+    /// 65-byte `eth_signTypedData_v4` signature
+    #[schema(example = json!("0x5f1c...1b"))]
+    pub signature: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/permit",
+    tag = "wallet",
+    description = "Return the EIP-712 typed data `owner` signs to grant DisperseCollect an allowance through EIP-2612 `permit`, replacing a separate approve transaction",
+    request_body = PermitRequest,
+    responses(
+        (status = 200, description = "Typed data for `eth_signTypedData_v4`", body = PermitTypedDataResponse),
+        (status = 400, description = "Invalid request or the token does not implement EIP-2612", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/permit")]
+async fn permit(req: web::Json<PermitRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    let erc20 = match ERC20::new(&config().network().rpc_url, req.token) {
+        Ok(erc20) => erc20,
+        Err(err) => {
+            error!(error = ?err, "Failed to create ERC20 contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create ERC20 contract"));
+        },
+    };
+
+    let amount = match erc20.parse_token(&req.amount).await {
+        Ok(amount) => amount,
+        Err(err) if err.is::<CallOutputError>() => return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string())),
+        Err(err) => {
+            error!(error = ?err, "Failed to parse amount");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse amount"));
+        },
+    };
+
+    let domain = match erc20.permit_domain().await {
+        Ok(Some(domain)) => domain,
+        Ok(None) => return HttpResponse::BadRequest().json(ErrorResponse::new("Token does not support EIP-2612 permit")),
+        Err(err) => {
+            error!(error = ?err, "Failed to detect permit support");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to detect permit support"));
+        },
+    };
+    let nonce = match erc20.query_nonces(req.owner).await {
+        Ok(nonce) => nonce,
+        Err(err) => {
+            error!(error = ?err, "Failed to query permit nonce");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query permit nonce"));
+        },
+    };
+
+    let deadline = req.deadline.unwrap_or_else(|| now() + DEFAULT_PERMIT_TTL_SECS);
+    let spender = disperse_collect.contract.address();
+    match ERC20::permit_typed_data(&domain, req.owner, spender, amount, nonce, deadline.into()) {
+        Ok(typed_data) => HttpResponse::Ok().json(PermitTypedDataResponse::new(typed_data, deadline)),
+        Err(err) => {
+            error!(error = ?err, "Failed to build permit typed data");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to build permit typed data"))
+        },
+    }
+}
+
+/// Checks each permit's signature against the token's current nonce for its owner and returns
+/// the signed amounts. Every owner may have one permit.
+async fn verify_permits(erc20: &ERC20, spender: Address, permits: &[PermitSignature]) -> Result<Vec<(U256, Signature)>, HttpResponse> {
+    let domain = match erc20.permit_domain().await {
+        Ok(Some(domain)) => domain,
+        Ok(None) => return Err(HttpResponse::BadRequest().json(ErrorResponse::new("Token does not support EIP-2612 permit"))),
+        Err(err) => {
+            error!(error = ?err, "Failed to detect permit support");
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to detect permit support")));
+        },
+    };
+
+    let mut verified = vec![];
+    for (i, signed_permit) in permits.iter().enumerate() {
+        // each permit spends the owner's nonce, a second one would revert on-chain
+        if permits[..i].iter().any(|earlier| earlier.owner == signed_permit.owner) {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("More than one permit of {:?}", signed_permit.owner))));
+        }
+        if signed_permit.deadline < now() {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Permit of {:?} has expired", signed_permit.owner))));
+        }
+        let Some(signature) = Signature::from_str(&signed_permit.signature).ok().filter(|signature| permit_v(signature.v).is_some()) else {
+            return Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Invalid permit signature of {:?}", signed_permit.owner))));
+        };
+        let amount = erc20.parse_token(&signed_permit.amount).await.map_err(|err| {
+            if err.is::<CallOutputError>() {
+                return HttpResponse::BadRequest().json(ErrorResponse::new(err.to_string()));
+            }
+            error!(error = ?err, "Failed to parse amount");
+            HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse permit amount"))
+        })?;
+        let nonce = erc20.query_nonces(signed_permit.owner).await.map_err(|err| {
+            error!(error = ?err, "Failed to query permit nonce");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query permit nonce"))
+        })?;
+
+        let typed_data = ERC20::permit_typed_data(&domain, signed_permit.owner, spender, amount, nonce, signed_permit.deadline.into()).map_err(|err| {
+            error!(error = ?err, "Failed to build permit typed data");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to build permit typed data"))
+        })?;
+        if EthProvider::recover_typed_data_signer(&typed_data, &signature).ok() != Some(signed_permit.owner) {
+            warn!(owner = ?signed_permit.owner, "Permit signature does not match its owner");
+            return Err(HttpResponse::BadRequest().json(ErrorResponse::new(format!("Permit of {:?} is not signed by the owner or uses a stale nonce", signed_permit.owner))));
+        }
+        verified.push((amount, signature));
+    }
+    Ok(verified)
+}
+
+/// Builds `permit` transactions for every signature followed by `tx`, which may rely on the
+/// permitted allowances. DisperseCollect has no permit entrypoint, so the permits can't be folded
+/// into `tx` itself; instead `sender` broadcasts the sequence and the owners never send an approve.
+///
+/// `required` lists how much each owner has to allow DisperseCollect. Owners without a permit
+/// must already have the allowance.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn build_with_permits(
+    erc20: &ERC20,
+    disperse_collect: &DisperseCollect,
+    endpoint: &str,
+    sender: Address,
+    fees: &FeeOptions,
+    permits: &[PermitSignature],
+    required: &[(Address, U256)],
+    tx: Result<TransactionRequest, Box<dyn std::error::Error>>,
+) -> HttpResponse {
+    if permits.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new("At least one permit is required"));
+    }
+    let spender = disperse_collect.contract.address();
+    let signed = match verify_permits(erc20, spender, permits).await {
+        Ok(signed) => signed,
+        Err(err) => return err,
+    };
+
+    for (owner, amount) in required {
+        match permits.iter().zip(signed.iter()).find(|(signed_permit, _)| signed_permit.owner == *owner) {
+            Some((_, (permitted, _))) if permitted < amount => {
+                return HttpResponse::BadRequest().json(ErrorResponse::new(format!("Permit of {owner:?} is below the required amount")));
+            },
+            Some(_) => {},
+            None => {
+                if let Err(err) = check_allowance(erc20, *owner, spender, *amount).await {
+                    return err;
+                }
+            },
+        }
+    }
+
+    let fees = match resolve_fees(endpoint, fees).await {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    let mut txs = vec![];
+    for (signed_permit, (amount, signature)) in permits.iter().zip(signed.iter()) {
+        let permit_tx = erc20.create_permit_tx(sender, signed_permit.owner, spender, *amount, signed_permit.deadline.into(), *signature);
+        match prepare_unsigned_tx(&erc20.provider, endpoint, sender, &fees, permit_tx).await {
+            Ok(permit_tx) => txs.push(permit_tx),
+            Err(err) => return err,
+        }
+    }
+
+    let allowances: Vec<(Address, U256)> = permits.iter().zip(signed.iter()).map(|(signed_permit, (amount, _))| (signed_permit.owner, *amount)).collect();
+    let state = match erc20.allowance_override(spender, &allowances).await {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Cannot simulate the token after the permit, send an approve instead"));
+        },
+        Err(err) => {
+            error!(error = ?err, "Failed to locate allowance storage");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to simulate transaction, the node may not support state overrides"));
+        },
+    };
+
+    build_unsigned_tx_after(&disperse_collect.provider, endpoint, sender, &fees, txs, tx, &state).await
+}
@@ -0,0 +1,116 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{disperse_collect::DisperseCollect, gas_oracle::FeeOptions, provider::EthProvider};
+use crate::utils::web3::{ether_to_string, parse_ether};
+use crate::utils::metrics;
+use crate::routes::pipeline::{prepare_unsigned_tx, resolve_fees};
+use crate::routes::checks::check_balance;
+use crate::routes::responses::{ErrorResponse, InsufficientFundsResponse, UnsignedTxResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SendEtherRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    #[schema(value_type = String, example = "0x2222222222222222222222222222222222222222")]
+    recipient: Address,
+    /// Amount in ether, required unless `send_max` is set
+    #[serde(default)]
+    #[schema(example = json!("1.5"))]
+    value: Option<String>,
+    /// Send the whole balance minus the transaction fee
+    #[serde(default)]
+    send_max: bool,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallet/sendEther",
+    tag = "wallet",
+    description = "Build a plain ether transfer from `sender` to `recipient`. The sender must cover `value` plus gas; with `send_max` the value is the balance minus the fee",
+    request_body = SendEtherRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance, gas or allowance checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/sendEther")]
+async fn send_ether(req: web::Json<SendEtherRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    let provider = &disperse_collect.provider;
+    let value = match (&req.value, req.send_max) {
+        (Some(_), true) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new("value and send_max are mutually exclusive"));
+        },
+        (None, false) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Either value or send_max is required"));
+        },
+        (Some(value), false) => match parse_ether(value) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                error!(error = ?err, "Failed to parse ether");
+                return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
+            },
+        },
+        (None, true) => None,
+    };
+
+    if let Some(value) = value {
+        if let Err(err) = check_balance(provider, req.sender, value).await {
+            return err;
+        }
+    }
+
+    // gas and fees are pinned on the tx so the fee checked here is the fee that gets signed
+    let tx = EthProvider::create_ether_transfer_tx(req.sender, req.recipient, value.unwrap_or_default());
+    let (gas, fees) = tokio::join!(provider.estimate_gas(&tx), resolve_fees("/wallet/sendEther", &req.fees));
+    let fees = match fees {
+        Ok(fees) => fees,
+        Err(err) => return err,
+    };
+    let gas = match gas {
+        Ok(gas) => gas,
+        Err(err) => {
+            error!(error = ?err, "Failed to estimate fee");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to estimate fee"));
+        },
+    };
+
+    // value plus fee is checked by the pipeline, send max has to leave room for the most the fee
+    // can be, which is what the node requires of the balance
+    let value = match value {
+        Some(value) => value,
+        None => {
+            let balance = match provider.get_balance(req.sender).await {
+                Ok(balance) => balance,
+                Err(err) => {
+                    error!(error = ?err, "Failed to query balance");
+                    return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query balance"));
+                },
+            };
+            let fee = gas * fees.max_fee_per_gas();
+            if balance <= fee {
+                return HttpResponse::BadRequest().json(InsufficientFundsResponse::new(
+                    "Balance does not cover the fee",
+                    ether_to_string(fee),
+                    ether_to_string(balance),
+                    ether_to_string(fee - balance),
+                ));
+            }
+            balance - fee
+        },
+    };
+
+    let tx = match prepare_unsigned_tx(provider, "/wallet/sendEther", req.sender, &fees, Ok(tx.value(value).gas(gas))).await {
+        Ok(tx) => tx,
+        Err(err) => return err,
+    };
+    let tx_hex = EthProvider::create_hex_tx(&tx);
+    metrics::record_tx_built("/wallet/sendEther");
+    HttpResponse::Ok().json(UnsignedTxResponse::new(tx, tx_hex))
+}
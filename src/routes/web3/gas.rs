@@ -0,0 +1,48 @@
+use actix_web::{get, HttpResponse, Responder};
+use tracing::error;
+
+use crate::config::config;
+use crate::contracts::gas_oracle::{GasOracle, GasTier};
+use crate::utils::web3::gwei_to_string;
+use crate::routes::responses::{ErrorResponse, GasFees, GasResponse};
+
+fn gas_fees(tier: &GasTier) -> GasFees {
+    GasFees::new(
+        gwei_to_string(tier.max_fee_per_gas),
+        gwei_to_string(tier.max_priority_fee_per_gas),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/web3/gas",
+    tag = "web3",
+    description = "Suggest slow, standard and fast fees from the priority fees paid in recent blocks (`eth_feeHistory`). Pass the tier as `speed` to any transaction-building endpoint, or set `max_fee_per_gas` and `max_priority_fee_per_gas` yourself",
+    responses(
+        (status = 200, description = "Fees of each tier in gwei", body = GasResponse),
+        (status = 500, description = "Provider call failed", body = ErrorResponse),
+    ),
+)]
+#[get("/gas")]
+async fn gas() -> impl Responder {
+    let oracle = match GasOracle::new(&config().network().rpc_url) {
+        Ok(oracle) => oracle,
+        Err(err) => {
+            error!(error = ?err, "Failed to create gas oracle");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create gas oracle"));
+        },
+    };
+    let suggestion = match oracle.suggest().await {
+        Ok(suggestion) => suggestion,
+        Err(err) => {
+            error!(error = ?err, "Failed to suggest gas fees");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to suggest gas fees"));
+        },
+    };
+    HttpResponse::Ok().json(GasResponse::new(
+        suggestion.base_fee.map(gwei_to_string),
+        gas_fees(&suggestion.slow),
+        gas_fees(&suggestion.standard),
+        gas_fees(&suggestion.fast),
+    ))
+}
@@ -1,9 +1,35 @@
 use actix_web::{web, Scope};
+use utoipa::OpenApi;
 
+use crate::config::config;
+
+mod gas;
 mod send_signed_transaction;
+mod sign_message;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    send_signed_transaction::send_signed_transaction,
+    send_signed_transaction::sign_transaction,
+    sign_message::verify_message,
+    sign_message::verify_typed_data,
+    sign_message::sign_message,
+    gas::gas,
+))]
+pub struct ApiDoc;
 
 pub fn route() -> Scope {
-    web::scope("/web3")
+    let mut scope = web::scope("/web3")
         .service(send_signed_transaction::send_signed_transaction)
-        .service(send_signed_transaction::sign_transaction)
+        .service(sign_message::verify_message)
+        .service(sign_message::verify_typed_data)
+        .service(gas::gas);
+    if config().features.sign_transaction {
+        scope = scope.service(send_signed_transaction::sign_transaction);
+    }
+    // signs with the server's key, so never without authentication
+    if config().signer.keystore_path.is_some() && config().auth.enabled {
+        scope = scope.service(sign_message::sign_message);
+    }
+    scope
 }
@@ -1,62 +1,125 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use ethers::{types::{transaction::eip2718::TypedTransaction, TransactionRequest}, utils::hex};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
 
 use crate::contracts::{disperse_collect::DisperseCollect, provider::EthProvider};
+use crate::utils::{logging::Secret, metrics};
+use crate::routes::responses::{ErrorResponse, SignedTxResponse, TxHashResponse};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 struct SendSignedTransactionRequest {
+    #[schema(example = json!("0x02f8..."))]
     signed_tx: String,
 }
 
-#[derive(Deserialize, Serialize)]
+/// A `tx` returned by the transaction builders, or a legacy transaction without `type`.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+enum UnsignedTransaction {
+    Typed(TypedTransaction),
+    Legacy(TransactionRequest),
+}
+
+impl From<UnsignedTransaction> for TypedTransaction {
+    fn from(tx: UnsignedTransaction) -> Self {
+        match tx {
+            UnsignedTransaction::Typed(tx) => tx,
+            UnsignedTransaction::Legacy(tx) => TypedTransaction::Legacy(tx),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
 struct SignTransactionRequest {
-    tx: TransactionRequest,
-    private_key: String,
+    #[schema(value_type = Object)]
+    tx: UnsignedTransaction,
+    #[schema(value_type = String, format = Password)]
+    private_key: Secret,
 }
 
+#[utoipa::path(
+    post,
+    path = "/web3/sendSignedTransaction",
+    tag = "web3",
+    description = "Broadcast a signed raw transaction",
+    request_body = SendSignedTransactionRequest,
+    responses(
+        (status = 200, description = "Transaction hash", body = TxHashResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/sendSignedTransaction")]
 async fn send_signed_transaction(req: web::Json<SendSignedTransactionRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
     let signed_tx = match hex::decode(&req.signed_tx) {
         Ok(signed_tx) => signed_tx,
         Err(err) => {
-            eprintln!("Failed to decode signed transaction: {err:?}");
-            return HttpResponse::BadRequest().json(json!({"status": "error", "message": "Failed to decode signed transaction" }));
+            error!(error = ?err, "Failed to decode signed transaction");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to decode signed transaction"));
         },
     };
     let tx_hash = match disperse_collect.provider.send_signed_transaction(signed_tx).await {
-        Ok(tx_hash) => tx_hash,
+        Ok(tx_hash) => {
+            metrics::record_broadcast(true);
+            tx_hash
+        },
         Err(err) => {
-            eprintln!("Failed to send signed transaction: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to send signed transaction" }));
+            metrics::record_broadcast(false);
+            error!(error = ?err, "Failed to send signed transaction");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to send signed transaction"));
         },
     };
 
-    HttpResponse::Ok().json(json!({"status": "success", "tx_hash": tx_hash }))
+    HttpResponse::Ok().json(TxHashResponse::new(tx_hash))
 }
 
 // sign transaction with private key (for testing)
+#[utoipa::path(
+    post,
+    path = "/web3/signTransaction",
+    tag = "web3",
+    description = "Sign a transaction with the given private key, for testing only",
+    request_body = SignTransactionRequest,
+    responses(
+        (status = 200, description = "Signed raw transaction", body = SignedTxResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
 #[post("/signTransaction")]
-async fn sign_transaction(req: web::Json<SignTransactionRequest>) -> impl Responder {
-    let wallet = match EthProvider::create_wallet(&req.private_key) {
+async fn sign_transaction(req: web::Json<SignTransactionRequest>, disperse_collect: web::Data<DisperseCollect>) -> impl Responder {
+    let wallet = match EthProvider::create_wallet(req.private_key.expose()) {
         Ok(wallet) => wallet,
         Err(err) => {
-            eprintln!("Failed to create wallet: {err:?}");
-            return HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to create wallet" }));
+            error!(error = ?err, "Failed to create wallet");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create wallet"));
         },
     };
 
-    let tx = TypedTransaction::Legacy(req.tx.clone());
+    // the builders' JSON leaves the chain id out, it is signed over so it comes from the node
+    let mut tx: TypedTransaction = req.tx.clone().into();
+    if tx.chain_id().is_none() {
+        match disperse_collect.provider.get_chain_id().await {
+            Ok(chain_id) => {
+                tx.set_chain_id(chain_id.as_u64());
+            },
+            Err(err) => {
+                error!(error = ?err, "Failed to get chain id");
+                return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to get chain id"));
+            },
+        }
+    }
 
     match EthProvider::sign_transaction(&wallet, &tx) {
         Ok(sign) => {
-            let signed_tx = EthProvider::create_hex_tx_from_signed(&req.tx, &sign);
-            HttpResponse::Ok().json(json!({"status": "success", "signed_tx": signed_tx }))
+            let signed_tx = EthProvider::create_hex_tx_from_signed(&tx, &sign);
+            HttpResponse::Ok().json(SignedTxResponse::new(signed_tx))
         },
         Err(err) => {
-            eprintln!("Failed to sign transaction: {err:?}");
-            HttpResponse::InternalServerError().json(json!({"status": "error", "message": "Failed to sign transaction" }))
+            error!(error = ?err, "Failed to sign transaction");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to sign transaction"))
         },
     }
 }
\ No newline at end of file
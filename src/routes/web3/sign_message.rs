@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::{types::{transaction::eip712::TypedData, Address, Signature}, utils::hex};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::{provider::EthProvider, signer::KeystoreSigner};
+use crate::routes::responses::{ErrorResponse, RecoveredSignerResponse, SignedMessageResponse};
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct VerifyMessageRequest {
+    #[schema(example = "Sign in to Wallet Manager")]
+    message: String,
+    /// `message` is 0x-prefixed hex bytes instead of UTF-8 text
+    #[serde(default)]
+    hex: bool,
+    #[schema(example = json!("0x5f1c...1b"))]
+    signature: String,
+    /// Expected signer, reported back in `matches`
+    #[schema(value_type = Option<String>, example = "0x1111111111111111111111111111111111111111")]
+    #[serde(default)]
+    address: Option<Address>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct VerifyTypedDataRequest {
+    /// Typed data in the `eth_signTypedData_v4` format
+    #[schema(value_type = Object)]
+    typed_data: TypedData,
+    #[schema(example = json!("0x5f1c...1b"))]
+    signature: String,
+    /// Expected signer, reported back in `matches`
+    #[schema(value_type = Option<String>, example = "0x1111111111111111111111111111111111111111")]
+    #[serde(default)]
+    address: Option<Address>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SignMessageRequest {
+    #[schema(example = "Payout batch 42 approved")]
+    message: String,
+    /// `message` is 0x-prefixed hex bytes instead of UTF-8 text
+    #[serde(default)]
+    hex: bool,
+}
+
+fn message_bytes(message: &str, is_hex: bool) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if is_hex {
+        Ok(hex::decode(message)?)
+    } else {
+        Ok(message.as_bytes().to_vec())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/web3/verifyMessage",
+    tag = "web3",
+    description = "Recover the address that signed `message` with `personal_sign` (EIP-191)",
+    request_body = VerifyMessageRequest,
+    responses(
+        (status = 200, description = "Recovered signer", body = RecoveredSignerResponse),
+        (status = 400, description = "Invalid message or signature", body = ErrorResponse),
+    ),
+)]
+#[post("/verifyMessage")]
+async fn verify_message(req: web::Json<VerifyMessageRequest>) -> impl Responder {
+    let message = match message_bytes(&req.message, req.hex) {
+        Ok(message) => message,
+        Err(err) => {
+            error!(error = ?err, "Failed to decode message");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to decode message"));
+        },
+    };
+    let signature = match Signature::from_str(&req.signature) {
+        Ok(signature) => signature,
+        Err(err) => {
+            error!(error = ?err, "Failed to parse signature");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse signature"));
+        },
+    };
+
+    match EthProvider::recover_message_signer(&message, &signature) {
+        Ok(signer) => HttpResponse::Ok().json(RecoveredSignerResponse::new(signer, req.address)),
+        Err(err) => {
+            error!(error = ?err, "Failed to recover signer");
+            HttpResponse::BadRequest().json(ErrorResponse::new("Failed to recover signer"))
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/web3/verifyTypedData",
+    tag = "web3",
+    description = "Recover the address that signed EIP-712 typed data with `eth_signTypedData_v4`",
+    request_body = VerifyTypedDataRequest,
+    responses(
+        (status = 200, description = "Recovered signer", body = RecoveredSignerResponse),
+        (status = 400, description = "Invalid typed data or signature", body = ErrorResponse),
+    ),
+)]
+#[post("/verifyTypedData")]
+async fn verify_typed_data(req: web::Json<VerifyTypedDataRequest>) -> impl Responder {
+    let signature = match Signature::from_str(&req.signature) {
+        Ok(signature) => signature,
+        Err(err) => {
+            error!(error = ?err, "Failed to parse signature");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse signature"));
+        },
+    };
+
+    match EthProvider::recover_typed_data_signer(&req.typed_data, &signature) {
+        Ok(signer) => HttpResponse::Ok().json(RecoveredSignerResponse::new(signer, req.address)),
+        Err(err) => {
+            error!(error = ?err, "Failed to recover signer");
+            HttpResponse::BadRequest().json(ErrorResponse::new("Failed to recover signer"))
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/web3/signMessage",
+    tag = "web3",
+    description = "Sign `message` with `personal_sign` (EIP-191) using the server's keystore signer. Only served when `signer.keystore_path` is configured",
+    request_body = SignMessageRequest,
+    responses(
+        (status = 200, description = "Signature and signer address", body = SignedMessageResponse),
+        (status = 400, description = "Invalid message", body = ErrorResponse),
+        (status = 500, description = "Signing failed", body = ErrorResponse),
+    ),
+)]
+#[post("/signMessage")]
+async fn sign_message(req: web::Json<SignMessageRequest>, signer: web::Data<KeystoreSigner>) -> impl Responder {
+    let message = match message_bytes(&req.message, req.hex) {
+        Ok(message) => message,
+        Err(err) => {
+            error!(error = ?err, "Failed to decode message");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to decode message"));
+        },
+    };
+
+    match signer.sign_message(&message).await {
+        Ok(signature) => HttpResponse::Ok().json(SignedMessageResponse::new(signer.address(), format!("0x{signature}"))),
+        Err(err) => {
+            error!(error = ?err, "Failed to sign message");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to sign message"))
+        },
+    }
+}
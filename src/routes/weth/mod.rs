@@ -0,0 +1,31 @@
+use actix_web::{web, Scope};
+use utoipa::OpenApi;
+
+use crate::config::config;
+use crate::contracts::weth::WETH;
+
+mod weth_deposit;
+mod weth_withdraw;
+mod weth_balance_of;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    weth_deposit::deposit,
+    weth_withdraw::withdraw,
+    weth_balance_of::balance_of,
+))]
+pub struct ApiDoc;
+
+pub fn route() -> Scope {
+    web::scope("/weth")
+        .service(weth_deposit::deposit)
+        .service(weth_withdraw::withdraw)
+        .service(weth_balance_of::balance_of)
+}
+
+/// The WETH contract of the selected network, an error when `weth_address` is not configured.
+pub(crate) fn network_weth() -> Result<WETH, Box<dyn std::error::Error>> {
+    let network = config().network();
+    let address = network.weth_address().ok_or_else(|| format!("No WETH address configured for network {}", network.name))?;
+    WETH::new(&network.rpc_url, address)
+}
@@ -0,0 +1,46 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::utils::web3::ether_to_string;
+use crate::routes::responses::{BalanceResponse, ErrorResponse};
+use super::network_weth;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct BalanceOfRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    owner: Address,
+}
+
+#[utoipa::path(
+    post,
+    path = "/weth/balanceOf",
+    tag = "weth",
+    description = "Query the WETH balance of `owner`",
+    request_body = BalanceOfRequest,
+    responses(
+        (status = 200, description = "Current balance in ether", body = BalanceResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/balanceOf")]
+async fn balance_of(req: web::Json<BalanceOfRequest>) -> impl Responder {
+    let weth = match network_weth() {
+        Ok(weth) => weth,
+        Err(err) => {
+            error!(error = ?err, "Failed to create WETH contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create WETH contract"));
+        },
+    };
+
+    match weth.query_balance_of(req.owner).await {
+        Ok(balance) => HttpResponse::Ok().json(BalanceResponse::new(ether_to_string(balance))),
+        Err(err) => {
+            error!(error = ?err, "Failed to query WETH balance");
+            HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to query WETH balance"))
+        },
+    }
+}
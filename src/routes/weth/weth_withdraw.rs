@@ -0,0 +1,60 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::contracts::gas_oracle::FeeOptions;
+use crate::utils::web3::parse_ether;
+use crate::routes::pipeline::build_unsigned_tx;
+use crate::routes::responses::{ErrorResponse, UnsignedTxResponse};
+use crate::routes::checks::check_weth_balance;
+use super::network_weth;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct WithdrawRequest {
+    #[schema(value_type = String, example = "0x1111111111111111111111111111111111111111")]
+    sender: Address,
+    /// WETH to unwrap
+    #[schema(example = json!("1.5"))]
+    value: String,
+    #[serde(flatten)]
+    fees: FeeOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/weth/withdraw",
+    tag = "weth",
+    description = "Build a WETH `withdraw` transaction unwrapping `value` WETH of `sender` back into ether",
+    request_body = WithdrawRequest,
+    responses(
+        (status = 200, description = "Unsigned transaction and its RLP encoding", body = UnsignedTxResponse),
+        (status = 400, description = "Invalid request or the transaction would revert. Failed balance or gas checks return an `InsufficientFundsResponse`", body = ErrorResponse),
+        (status = 500, description = "Provider or contract call failed", body = ErrorResponse),
+    ),
+)]
+#[post("/withdraw")]
+async fn withdraw(req: web::Json<WithdrawRequest>) -> impl Responder {
+    let weth = match network_weth() {
+        Ok(weth) => weth,
+        Err(err) => {
+            error!(error = ?err, "Failed to create WETH contract");
+            return HttpResponse::InternalServerError().json(ErrorResponse::new("Failed to create WETH contract"));
+        },
+    };
+    let value = match parse_ether(&req.value) {
+        Ok(value) => value,
+        Err(err) => {
+            error!(error = ?err, "Failed to parse ether");
+            return HttpResponse::BadRequest().json(ErrorResponse::new("Failed to parse ether"));
+        },
+    };
+
+    if let Err(err) = check_weth_balance(&weth, req.sender, value).await {
+        return err;
+    }
+
+    let tx = weth.create_withdraw_tx(req.sender, value);
+    build_unsigned_tx(&weth.provider, "/weth/withdraw", req.sender, &req.fees, tx).await
+}
@@ -0,0 +1,873 @@
+use std::{collections::HashMap, env, fmt, fs, path::Path, str::FromStr, time::Duration};
+
+use clap::{Parser, Subcommand};
+use ethers::types::{Address, H256, U256};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use tracing::info;
+use url::Url;
+
+use crate::auth::api_keys::ApiScope;
+use super::logging::redact_url;
+
+const DEFAULT_CONFIG_FILE: &str = "./config/backend.toml";
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Command line flags, applied on top of the config file and environment.
+#[derive(Parser, Debug, Default)]
+#[command(version, about = "Wallet manager backend")]
+pub struct Cli {
+    /// Path to the TOML config file (default ./config/backend.toml, env CONFIG_FILE)
+    #[arg(long)]
+    pub config: Option<String>,
+    #[arg(long)]
+    pub bind_address: Option<String>,
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Name of the network used by the API
+    #[arg(long)]
+    pub network: Option<String>,
+    #[arg(long)]
+    pub rpc_url: Option<String>,
+    #[arg(long)]
+    pub disperse_collect_address: Option<String>,
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+    #[arg(long)]
+    pub abi_dir: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage API keys in the configured keys file
+    #[command(subcommand)]
+    Keys(KeysCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommand {
+    /// Issue a key and print it once
+    Issue {
+        #[arg(long)]
+        name: String,
+        /// Comma-separated: read, build, broadcast, sign, admin
+        #[arg(long, value_delimiter = ',', required = true)]
+        scopes: Vec<ApiScope>,
+    },
+    /// Revoke a key by id
+    Revoke { id: String },
+    /// List keys without their secrets
+    List,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub port: u16,
+    pub workers: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 8000,
+            workers: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Allow any origin, method and header (development only)
+    pub permissive: bool,
+    /// Exact origins such as `https://app.example.com`, or `*`
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Added to `Content-Type`, `Authorization` and `X-API-Key`, which are always allowed
+    pub allowed_headers: Vec<String>,
+    pub supports_credentials: bool,
+    pub max_age_secs: usize,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            permissive: false,
+            allowed_origins: vec![],
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allowed_headers: vec![],
+            supports_credentials: false,
+            max_age_secs: 3600,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    pub name: String,
+    /// hex-encoded sha256 of the key
+    pub key_hash: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Require an API key (`X-API-Key` or `Authorization: Bearer`) on every non-public route
+    pub enabled: bool,
+    /// Keys issued through `backend keys issue` or `/admin/keys`, which is only served when `enabled`
+    pub keys_file: String,
+    pub keys: Vec<ApiKeyConfig>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keys_file: "./config/api_keys.json".to_string(),
+            keys: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Sustained rate per API key, or per IP for anonymous callers
+    pub requests_per_minute: u32,
+    /// Bucket size, the most tokens a client can spend at once
+    pub burst: u32,
+    /// Base token cost per path, overriding the built-in costs of expensive routes. Routes fanning
+    /// out over recipients, contributors or token ids still add a cost per entry.
+    pub route_costs: HashMap<String, f64>,
+    /// `requests_per_minute` per API key name
+    pub clients: HashMap<String, u32>,
+    /// Tokens a client may spend per day, unlimited when unset. Kept in memory, so it restarts
+    /// with the server.
+    pub daily_quota: Option<u32>,
+    /// `daily_quota` per API key name
+    pub client_quotas: HashMap<String, u32>,
+    /// Failed API key checks allowed per IP and minute before the IP is turned away
+    pub auth_failures_per_minute: u32,
+    /// Use `X-Forwarded-For`/`Forwarded` for the client IP, only behind a trusted proxy
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            requests_per_minute: 120,
+            burst: 30,
+            route_costs: HashMap::new(),
+            clients: HashMap::new(),
+            daily_quota: None,
+            client_quotas: HashMap::new(),
+            auth_failures_per_minute: 10,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub chain_id: Option<u64>,
+    pub rpc_url: String,
+    pub disperse_collect_address: String,
+    /// keccak256 of known DisperseCollect runtime bytecode, empty to skip the check
+    pub disperse_collect_code_hashes: Vec<String>,
+    /// Wrapped native token (WETH9) of the network, the `/weth` routes are disabled without it
+    pub weth_address: Option<String>,
+    /// Uniswap token-list JSON of known tokens, entries for other chains are skipped
+    pub token_list: Option<String>,
+    /// Reject ERC20 tokens missing from `token_list` or whose on-chain decimals or symbol disagree
+    pub token_list_strict: bool,
+}
+
+impl NetworkConfig {
+    pub fn disperse_collect_code_hashes(&self) -> Vec<H256> {
+        self.disperse_collect_code_hashes.iter().filter_map(|hash| hash.parse().ok()).collect()
+    }
+
+    pub fn weth_address(&self) -> Option<Address> {
+        self.weth_address.as_deref().and_then(|address| address.parse().ok())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AbiConfig {
+    pub dir: String,
+}
+
+impl Default for AbiConfig {
+    fn default() -> Self {
+        Self { dir: "./config/abi".to_string() }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    pub rpc_timeout_ms: u64,
+    pub batch_window_ms: u64,
+    pub gas_price_ttl_ms: u64,
+    /// How long the latest block number is reused, balances are read and cached at that block
+    pub block_number_ttl_ms: u64,
+    pub client_request_timeout_ms: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            rpc_timeout_ms: 30_000,
+            batch_window_ms: 2,
+            gas_price_ttl_ms: 5_000,
+            block_number_ttl_ms: 1_000,
+            client_request_timeout_ms: 5_000,
+        }
+    }
+}
+
+impl TimeoutsConfig {
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms)
+    }
+
+    pub fn batch_window(&self) -> Duration {
+        Duration::from_millis(self.batch_window_ms)
+    }
+
+    pub fn gas_price_ttl(&self) -> Duration {
+        Duration::from_millis(self.gas_price_ttl_ms)
+    }
+
+    pub fn block_number_ttl(&self) -> Duration {
+        Duration::from_millis(self.block_number_ttl_ms)
+    }
+
+    pub fn client_request_timeout(&self) -> Duration {
+        Duration::from_millis(self.client_request_timeout_ms)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasConfig {
+    /// Recent blocks of `eth_feeHistory` the priority fee suggestions are taken from
+    pub fee_history_blocks: u64,
+    /// Priority fee percentiles of the slow, standard and fast tiers
+    pub percentiles: [f64; 3],
+    /// Gas limits are `eth_estimateGas` times this, for state that changes before inclusion
+    pub estimate_multiplier: f64,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            fee_history_blocks: 20,
+            percentiles: [10.0, 50.0, 90.0],
+            estimate_multiplier: 1.0,
+        }
+    }
+}
+
+impl GasConfig {
+    /// `gas` scaled by `estimate_multiplier`, rounded up.
+    pub fn with_margin(&self, gas: U256) -> U256 {
+        let per_mille = (self.estimate_multiplier * 1000.0).ceil() as u64;
+        (gas * per_mille + 999) / 1000
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// Serve `/web3/signTransaction`, which takes a raw private key (testing only). Off by default
+    /// so a fresh deployment doesn't expose it to anyone who can reach the port.
+    pub sign_transaction: bool,
+    pub metrics: bool,
+    /// Refuse to start when the DisperseCollect deployment check fails
+    pub strict_startup: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            sign_transaction: false,
+            metrics: true,
+            strict_startup: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiweConfig {
+    /// Require a Sign-In with Ethereum session on `/wallet` and `/token`, and that request
+    /// `sender` fields equal the signed-in address
+    pub enabled: bool,
+    /// The `domain` users sign in to, e.g. `app.example.com`
+    pub domain: String,
+    pub nonce_ttl_secs: u64,
+    /// Sessions end after this long, or at the message's expiration time if that is earlier
+    pub session_ttl_secs: u64,
+}
+
+impl Default for SiweConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            domain: String::new(),
+            nonce_ttl_secs: 300,
+            session_ttl_secs: 86_400,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignerConfig {
+    /// Encrypted JSON keystore used by `/web3/signMessage`, which is only served when set. Requires
+    /// `auth.enabled`, anyone could sign with the server's key otherwise.
+    pub keystore_path: Option<String>,
+    /// Environment variable holding the keystore password
+    pub password_env: String,
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            keystore_path: None,
+            password_env: "KEYSTORE_PASSWORD".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Name of the entry in `networks` used by the API
+    pub network: String,
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub auth: AuthConfig,
+    pub siwe: SiweConfig,
+    pub rate_limit: RateLimitConfig,
+    pub networks: Vec<NetworkConfig>,
+    pub abi: AbiConfig,
+    pub timeouts: TimeoutsConfig,
+    pub gas: GasConfig,
+    pub features: FeaturesConfig,
+    pub signer: SignerConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            network: "default".to_string(),
+            server: ServerConfig::default(),
+            cors: CorsConfig::default(),
+            auth: AuthConfig::default(),
+            siwe: SiweConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            networks: vec![],
+            abi: AbiConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            gas: GasConfig::default(),
+            features: FeaturesConfig::default(),
+            signer: SignerConfig::default(),
+        }
+    }
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<String>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+        for error in self.0.iter() {
+            writeln!(f, "  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+fn env_override<T: FromStr>(name: &str, target: &mut T, errors: &mut Vec<String>) {
+    if let Ok(value) = env::var(name) {
+        match value.parse() {
+            Ok(value) => *target = value,
+            Err(_) => errors.push(format!("{name}: cannot parse {value:?}")),
+        }
+    }
+}
+
+fn env_list(name: &str) -> Option<Vec<String>> {
+    env::var(name).ok().map(|value| {
+        value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+    })
+}
+
+impl Config {
+    /// Loads defaults, then the TOML file, then environment variables, then CLI flags,
+    /// and validates the result.
+    pub fn load(cli: &Cli) -> Result<Self, ConfigErrors> {
+        let (config, mut errors) = Self::load_unvalidated(cli);
+        config.validate(&mut errors);
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(errors))
+        }
+    }
+
+    /// Merges all layers without validating, for tools that only need part of the config.
+    pub fn load_unvalidated(cli: &Cli) -> (Self, Vec<String>) {
+        let mut errors = vec![];
+
+        let explicit_path = cli.config.clone().or_else(|| env::var("CONFIG_FILE").ok());
+        let path = explicit_path.clone().unwrap_or(DEFAULT_CONFIG_FILE.to_string());
+        let mut config = if Path::new(&path).exists() {
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|s| toml::from_str(&s).map_err(|e| e.to_string())) {
+                Ok(config) => config,
+                Err(err) => {
+                    errors.push(format!("{path}: {err}"));
+                    Config::default()
+                },
+            }
+        } else {
+            if explicit_path.is_some() {
+                errors.push(format!("{path}: config file not found"));
+            }
+            Config::default()
+        };
+
+        config.apply_env(&mut errors);
+        if let Some(network) = &cli.network {
+            config.network = network.clone();
+        }
+        config.apply_network_env(&mut errors);
+        config.apply_cli(cli);
+        (config, errors)
+    }
+
+    fn network_mut(&mut self) -> &mut NetworkConfig {
+        let name = self.network.clone();
+        if let Some(index) = self.networks.iter().position(|network| network.name == name) {
+            return &mut self.networks[index];
+        }
+        self.networks.push(NetworkConfig { name, ..Default::default() });
+        self.networks.last_mut().unwrap()
+    }
+
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        env_override("NETWORK", &mut self.network, errors);
+        env_override("BIND_ADDRESS", &mut self.server.bind_address, errors);
+        env_override("PORT", &mut self.server.port, errors);
+        env_override("ABI_DIR", &mut self.abi.dir, errors);
+        env_override("STRICT_STARTUP", &mut self.features.strict_startup, errors);
+        env_override("AUTH_ENABLED", &mut self.auth.enabled, errors);
+        env_override("API_KEYS_FILE", &mut self.auth.keys_file, errors);
+        env_override("SIWE_ENABLED", &mut self.siwe.enabled, errors);
+        env_override("SIWE_DOMAIN", &mut self.siwe.domain, errors);
+        env_override("RATE_LIMIT_ENABLED", &mut self.rate_limit.enabled, errors);
+        env_override("RATE_LIMIT_REQUESTS_PER_MINUTE", &mut self.rate_limit.requests_per_minute, errors);
+        env_override("GAS_FEE_HISTORY_BLOCKS", &mut self.gas.fee_history_blocks, errors);
+        env_override("GAS_ESTIMATE_MULTIPLIER", &mut self.gas.estimate_multiplier, errors);
+        env_override("CORS_PERMISSIVE", &mut self.cors.permissive, errors);
+        if let Some(origins) = env_list("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins;
+        }
+        if let Some(headers) = env_list("CORS_ALLOWED_HEADERS") {
+            self.cors.allowed_headers = headers;
+        }
+        env_override("CORS_SUPPORTS_CREDENTIALS", &mut self.cors.supports_credentials, errors);
+        env_override("CORS_MAX_AGE_SECS", &mut self.cors.max_age_secs, errors);
+        if let Ok(path) = env::var("KEYSTORE_PATH") {
+            self.signer.keystore_path = Some(path);
+        }
+    }
+
+    // the original single-network variables keep working and target the selected network
+    fn apply_network_env(&mut self, errors: &mut Vec<String>) {
+        let names = [
+            "RPC_PROVIDER_URL", "DISPERSE_COLLECT_CONTRACT_ADDRESS", "CHAIN_ID", "DISPERSE_COLLECT_CODE_HASHES",
+            "WETH_ADDRESS", "TOKEN_LIST", "TOKEN_LIST_STRICT",
+        ];
+        if !names.iter().any(|name| env::var(name).is_ok()) {
+            return;
+        }
+
+        let mut network = self.network_mut().clone();
+        env_override("RPC_PROVIDER_URL", &mut network.rpc_url, errors);
+        env_override("DISPERSE_COLLECT_CONTRACT_ADDRESS", &mut network.disperse_collect_address, errors);
+        if let Ok(chain_id) = env::var("CHAIN_ID") {
+            match chain_id.parse() {
+                Ok(chain_id) => network.chain_id = Some(chain_id),
+                Err(_) => errors.push(format!("CHAIN_ID: cannot parse {chain_id:?}")),
+            }
+        }
+        if let Some(hashes) = env_list("DISPERSE_COLLECT_CODE_HASHES") {
+            network.disperse_collect_code_hashes = hashes;
+        }
+        if let Ok(address) = env::var("WETH_ADDRESS") {
+            network.weth_address = Some(address);
+        }
+        if let Ok(path) = env::var("TOKEN_LIST") {
+            network.token_list = Some(path);
+        }
+        env_override("TOKEN_LIST_STRICT", &mut network.token_list_strict, errors);
+        *self.network_mut() = network;
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(bind_address) = &cli.bind_address {
+            self.server.bind_address = bind_address.clone();
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(abi_dir) = &cli.abi_dir {
+            self.abi.dir = abi_dir.clone();
+        }
+        if cli.rpc_url.is_some() || cli.disperse_collect_address.is_some() || cli.chain_id.is_some() {
+            let network = self.network_mut();
+            if let Some(rpc_url) = &cli.rpc_url {
+                network.rpc_url = rpc_url.clone();
+            }
+            if let Some(address) = &cli.disperse_collect_address {
+                network.disperse_collect_address = address.clone();
+            }
+            if let Some(chain_id) = cli.chain_id {
+                network.chain_id = Some(chain_id);
+            }
+        }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.server.bind_address.parse::<std::net::IpAddr>().is_err() {
+            errors.push(format!("server.bind_address: {:?} is not an IP address", self.server.bind_address));
+        }
+        if self.server.port == 0 {
+            errors.push("server.port: must not be 0".to_string());
+        }
+        if self.server.workers == Some(0) {
+            errors.push("server.workers: must not be 0".to_string());
+        }
+
+        for origin in self.cors.allowed_origins.iter() {
+            if origin != "*" && Url::parse(origin).is_err() {
+                errors.push(format!("cors.allowed_origins: {origin:?} is not a valid origin"));
+            }
+        }
+        if self.cors.supports_credentials && self.cors.allowed_origins.iter().any(|origin| origin == "*") {
+            errors.push("cors.supports_credentials: cannot be combined with the \"*\" origin".to_string());
+        }
+        for method in self.cors.allowed_methods.iter() {
+            if actix_web::http::Method::from_bytes(method.as_bytes()).is_err() {
+                errors.push(format!("cors.allowed_methods: {method:?} is not an HTTP method"));
+            }
+        }
+        for name in self.cors.allowed_headers.iter() {
+            if actix_web::http::header::HeaderName::try_from(name.as_str()).is_err() {
+                errors.push(format!("cors.allowed_headers: {name:?} is not a valid header name"));
+            }
+        }
+
+        for (index, key) in self.auth.keys.iter().enumerate() {
+            if key.name.is_empty() {
+                errors.push(format!("auth.keys[{index}].name: must not be empty"));
+            }
+            if key.key_hash.len() != 64 || !key.key_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                errors.push(format!("auth.keys[{index}].key_hash: must be a hex-encoded sha256"));
+            }
+            if key.scopes.is_empty() {
+                errors.push(format!("auth.keys[{index}].scopes: must not be empty"));
+            }
+        }
+
+        if self.siwe.enabled && self.siwe.domain.is_empty() {
+            errors.push("siwe.domain: must be set when siwe.enabled (SIWE_DOMAIN)".to_string());
+        }
+        if self.siwe.nonce_ttl_secs == 0 {
+            errors.push("siwe.nonce_ttl_secs: must be greater than 0".to_string());
+        }
+        if self.siwe.session_ttl_secs == 0 {
+            errors.push("siwe.session_ttl_secs: must be greater than 0".to_string());
+        }
+
+        if self.rate_limit.requests_per_minute == 0 {
+            errors.push("rate_limit.requests_per_minute: must be greater than 0".to_string());
+        }
+        if self.rate_limit.burst == 0 {
+            errors.push("rate_limit.burst: must be greater than 0".to_string());
+        }
+        for (path, cost) in self.rate_limit.route_costs.iter() {
+            if !cost.is_finite() || *cost < 0.0 {
+                errors.push(format!("rate_limit.route_costs.{path:?}: must be a non-negative number"));
+            }
+        }
+        for (name, requests_per_minute) in self.rate_limit.clients.iter() {
+            if *requests_per_minute == 0 {
+                errors.push(format!("rate_limit.clients.{name:?}: must be greater than 0"));
+            }
+        }
+        if self.rate_limit.daily_quota == Some(0) {
+            errors.push("rate_limit.daily_quota: must be greater than 0".to_string());
+        }
+        for (name, quota) in self.rate_limit.client_quotas.iter() {
+            if *quota == 0 {
+                errors.push(format!("rate_limit.client_quotas.{name:?}: must be greater than 0"));
+            }
+        }
+        if self.rate_limit.auth_failures_per_minute == 0 {
+            errors.push("rate_limit.auth_failures_per_minute: must be greater than 0".to_string());
+        }
+
+        if !self.networks.iter().any(|network| network.name == self.network) {
+            errors.push(format!(
+                "network: {:?} is not defined in networks (add a [[networks]] entry or set RPC_PROVIDER_URL and DISPERSE_COLLECT_CONTRACT_ADDRESS)",
+                self.network,
+            ));
+        }
+        for (index, network) in self.networks.iter().enumerate() {
+            let prefix = format!("networks[{index}] ({})", network.name);
+            if network.name.is_empty() {
+                errors.push(format!("{prefix}.name: must not be empty"));
+            }
+            if self.networks.iter().filter(|other| other.name == network.name).count() > 1 {
+                errors.push(format!("{prefix}.name: defined more than once"));
+            }
+            match Url::parse(&network.rpc_url) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {},
+                Ok(_) => errors.push(format!("{prefix}.rpc_url: only http(s) URLs are supported")),
+                Err(_) if network.rpc_url.is_empty() => errors.push(format!("{prefix}.rpc_url: not set (RPC_PROVIDER_URL)")),
+                Err(err) => errors.push(format!("{prefix}.rpc_url: {err}")),
+            }
+            if network.disperse_collect_address.is_empty() {
+                errors.push(format!("{prefix}.disperse_collect_address: not set (DISPERSE_COLLECT_CONTRACT_ADDRESS)"));
+            } else if network.disperse_collect_address.parse::<Address>().is_err() {
+                errors.push(format!("{prefix}.disperse_collect_address: {:?} is not an address", network.disperse_collect_address));
+            }
+            for hash in network.disperse_collect_code_hashes.iter() {
+                if hash.parse::<H256>().is_err() {
+                    errors.push(format!("{prefix}.disperse_collect_code_hashes: {hash:?} is not a 32-byte hash"));
+                }
+            }
+            if let Some(address) = &network.weth_address {
+                if address.parse::<Address>().is_err() {
+                    errors.push(format!("{prefix}.weth_address: {address:?} is not an address"));
+                }
+            }
+            match &network.token_list {
+                Some(path) if !Path::new(path).is_file() => errors.push(format!("{prefix}.token_list: {path:?} is not a file")),
+                None if network.token_list_strict => errors.push(format!("{prefix}.token_list_strict: requires token_list (TOKEN_LIST)")),
+                _ => {},
+            }
+        }
+
+        let abi_dir = Path::new(&self.abi.dir);
+        if !abi_dir.is_dir() {
+            errors.push(format!("abi.dir: {:?} is not a directory", self.abi.dir));
+        } else {
+            for name in crate::contracts::ABI_NAMES {
+                if !abi_dir.join(format!("{name}.json")).is_file() {
+                    errors.push(format!("abi.dir: {name}.json is missing in {:?}", self.abi.dir));
+                }
+            }
+        }
+
+        if let Some(path) = &self.signer.keystore_path {
+            if !Path::new(path).is_file() {
+                errors.push(format!("signer.keystore_path: {path:?} is not a file"));
+            }
+            if env::var(&self.signer.password_env).is_err() {
+                errors.push(format!("signer.password_env: {} is not set", self.signer.password_env));
+            }
+            if !self.auth.enabled {
+                errors.push("signer.keystore_path: requires auth.enabled, /web3/signMessage would sign for anyone".to_string());
+            }
+        }
+
+        let timeouts = [
+            ("rpc_timeout_ms", self.timeouts.rpc_timeout_ms),
+            ("batch_window_ms", self.timeouts.batch_window_ms),
+            ("gas_price_ttl_ms", self.timeouts.gas_price_ttl_ms),
+            ("block_number_ttl_ms", self.timeouts.block_number_ttl_ms),
+            ("client_request_timeout_ms", self.timeouts.client_request_timeout_ms),
+        ];
+        for (name, value) in timeouts {
+            if value == 0 {
+                errors.push(format!("timeouts.{name}: must be greater than 0"));
+            }
+        }
+
+        if !(1..=1024).contains(&self.gas.fee_history_blocks) {
+            errors.push("gas.fee_history_blocks: must be between 1 and 1024".to_string());
+        }
+        if self.gas.percentiles.iter().any(|percentile| !(0.0..=100.0).contains(percentile)) {
+            errors.push("gas.percentiles: must be between 0 and 100".to_string());
+        } else if !self.gas.percentiles.windows(2).all(|pair| pair[0] <= pair[1]) {
+            errors.push("gas.percentiles: must be in ascending order (slow, standard, fast)".to_string());
+        }
+        if !(1.0..=10.0).contains(&self.gas.estimate_multiplier) {
+            errors.push("gas.estimate_multiplier: must be between 1 and 10".to_string());
+        }
+    }
+
+    /// The network selected by `network`; always present after validation.
+    pub fn network(&self) -> &NetworkConfig {
+        self.networks
+            .iter()
+            .find(|network| network.name == self.network)
+            .expect("Selected network is validated on load")
+    }
+
+    pub fn log_summary(&self) {
+        let network = self.network();
+        info!(
+            bind_address = %self.server.bind_address,
+            port = self.server.port,
+            network = %network.name,
+            chain_id = ?network.chain_id,
+            rpc_provider_url = %redact_url(&network.rpc_url),
+            disperse_collect_contract_address = %network.disperse_collect_address,
+            known_code_hashes = network.disperse_collect_code_hashes.len(),
+            weth_address = ?network.weth_address,
+            token_list = ?network.token_list,
+            token_list_strict = network.token_list_strict,
+            abi_dir = %self.abi.dir,
+            auth_enabled = self.auth.enabled,
+            siwe_enabled = self.siwe.enabled,
+            cors_permissive = self.cors.permissive,
+            cors_allowed_origins = ?self.cors.allowed_origins,
+            gas_estimate_multiplier = self.gas.estimate_multiplier,
+            features = ?self.features,
+            keystore_signer = self.signer.keystore_path.is_some(),
+            "Loaded configuration"
+        );
+    }
+}
+
+/// Installs the configuration used by the rest of the application.
+pub fn init(config: Config) {
+    CONFIG.set(config).expect("Configuration is initialized once");
+}
+
+pub fn config() -> &'static Config {
+    CONFIG.get().expect("Configuration is not initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid() -> Config {
+        Config {
+            networks: vec![NetworkConfig {
+                name: "default".to_string(),
+                rpc_url: "http://127.0.0.1:8545".to_string(),
+                disperse_collect_address: "0x00000000000000000000000000000000000000aa".to_string(),
+                ..NetworkConfig::default()
+            }],
+            ..Config::default()
+        }
+    }
+
+    fn errors(config: &Config) -> Vec<String> {
+        let mut errors = vec![];
+        config.validate(&mut errors);
+        errors
+    }
+
+    #[test]
+    fn accepts_the_defaults_with_a_network() {
+        assert_eq!(errors(&valid()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn parses_the_example_config() {
+        let example = fs::read_to_string("./config/backend.example.toml").unwrap();
+        let config: Config = toml::from_str(&example).unwrap();
+        assert_eq!(errors(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let mut config = valid();
+        config.server.port = 0;
+        config.cors.allowed_origins = vec!["not an origin".to_string()];
+        config.rate_limit.burst = 0;
+        config.timeouts.rpc_timeout_ms = 0;
+        config.gas.percentiles = [50.0, 10.0, 90.0];
+        config.networks[0].rpc_url = "ws://127.0.0.1:8546".to_string();
+        config.networks[0].disperse_collect_address = "0x12".to_string();
+
+        assert_eq!(errors(&config), vec![
+            "server.port: must not be 0",
+            "cors.allowed_origins: \"not an origin\" is not a valid origin",
+            "rate_limit.burst: must be greater than 0",
+            "networks[0] (default).rpc_url: only http(s) URLs are supported",
+            "networks[0] (default).disperse_collect_address: \"0x12\" is not an address",
+            "timeouts.rpc_timeout_ms: must be greater than 0",
+            "gas.percentiles: must be in ascending order (slow, standard, fast)",
+        ]);
+    }
+
+    #[test]
+    fn requires_the_selected_network() {
+        let mut config = valid();
+        config.network = "sepolia".to_string();
+        assert!(errors(&config)[0].starts_with("network: \"sepolia\" is not defined"));
+
+        config.networks.push(NetworkConfig { name: "sepolia".to_string(), ..config.networks[0].clone() });
+        config.networks.push(config.networks[1].clone());
+        assert_eq!(errors(&config), vec![
+            "networks[1] (sepolia).name: defined more than once",
+            "networks[2] (sepolia).name: defined more than once",
+        ]);
+    }
+
+    #[test]
+    fn requires_auth_for_the_keystore_signer() {
+        let mut config = valid();
+        config.signer.keystore_path = Some("./Cargo.toml".to_string());
+        config.signer.password_env = "PATH".to_string();
+        assert_eq!(errors(&config), vec!["signer.keystore_path: requires auth.enabled, /web3/signMessage would sign for anyone"]);
+
+        config.auth.enabled = true;
+        assert_eq!(errors(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_credentials_with_any_origin() {
+        let mut config = valid();
+        config.cors.allowed_origins = vec!["*".to_string()];
+        config.cors.supports_credentials = true;
+        assert_eq!(errors(&config), vec!["cors.supports_credentials: cannot be combined with the \"*\" origin"]);
+    }
+
+    #[test]
+    fn gas_margin_rounds_up() {
+        let gas = |estimate_multiplier| GasConfig { estimate_multiplier, ..GasConfig::default() };
+        assert_eq!(gas(1.0).with_margin(21_000.into()), 21_000.into());
+        assert_eq!(gas(1.2).with_margin(21_000.into()), 25_200.into());
+        assert_eq!(gas(1.25).with_margin(21_001.into()), 26_252.into());
+        assert_eq!(gas(1.0001).with_margin(100.into()), 101.into());
+    }
+}
@@ -0,0 +1,40 @@
+use actix_cors::Cors;
+use actix_web::http::header::{self, HeaderName};
+
+use crate::auth::{middleware::API_KEY_HEADER, siwe::SESSION_HEADER};
+use crate::config::CorsConfig;
+
+/// Builds the CORS middleware from config. `permissive` reflects any origin and is meant for local development.
+pub fn build_cors(cors_config: &CorsConfig) -> Cors {
+    if cors_config.permissive {
+        return Cors::permissive();
+    }
+
+    let mut cors = Cors::default()
+        .allowed_methods(cors_config.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(allowed_headers(cors_config))
+        .expose_headers(vec![header::RETRY_AFTER])
+        .max_age(cors_config.max_age_secs);
+    for origin in cors_config.allowed_origins.iter() {
+        cors = match origin.as_str() {
+            "*" => cors.allow_any_origin(),
+            origin => cors.allowed_origin(origin),
+        };
+    }
+    if cors_config.supports_credentials {
+        cors = cors.supports_credentials();
+    }
+    cors
+}
+
+fn allowed_headers(cors_config: &CorsConfig) -> Vec<HeaderName> {
+    let mut headers = vec![header::CONTENT_TYPE, header::AUTHORIZATION, HeaderName::from_static(API_KEY_HEADER), HeaderName::from_static(SESSION_HEADER)];
+    for name in cors_config.allowed_headers.iter() {
+        if let Ok(name) = HeaderName::try_from(name.as_str()) {
+            if !headers.contains(&name) {
+                headers.push(name);
+            }
+        }
+    }
+    headers
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use actix_web::dev::ServiceResponse;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+lazy_static! {
+    pub static ref REGISTRY: Registry = Registry::new();
+
+    pub static ref HTTP_REQUESTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("http_requests_total", "HTTP requests by route, method and status"),
+        &["route", "method", "status"],
+    ).unwrap());
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register(HistogramVec::new(
+        HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route and method"),
+        &["route", "method"],
+    ).unwrap());
+
+    pub static ref RPC_REQUESTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("rpc_requests_total", "JSON-RPC calls sent to the provider by method"),
+        &["method"],
+    ).unwrap());
+    pub static ref RPC_ERRORS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("rpc_errors_total", "Failed JSON-RPC calls by method"),
+        &["method"],
+    ).unwrap());
+    pub static ref RPC_REQUEST_DURATION: HistogramVec = register(HistogramVec::new(
+        HistogramOpts::new("rpc_request_duration_seconds", "JSON-RPC call latency by method"),
+        &["method"],
+    ).unwrap());
+    pub static ref RPC_CACHE_HITS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("rpc_cache_hits_total", "JSON-RPC calls served from the transport cache"),
+        &["method"],
+    ).unwrap());
+    pub static ref RPC_CACHE_MISSES: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("rpc_cache_misses_total", "Cacheable JSON-RPC calls not found in the transport cache"),
+        &["method"],
+    ).unwrap());
+    pub static ref RPC_BATCHES: IntCounter = register(IntCounter::new(
+        "rpc_batches_total", "HTTP requests sent to the provider by the batching transport",
+    ).unwrap());
+    pub static ref RPC_BATCH_SIZE: Histogram = register(Histogram::with_opts(
+        HistogramOpts::new("rpc_batch_size", "Number of JSON-RPC calls per batch")
+            .buckets(vec![1.0, 2.0, 3.0, 5.0, 10.0, 25.0, 50.0, 100.0]),
+    ).unwrap());
+
+    pub static ref TXS_BUILT: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("txs_built_total", "Unsigned transactions built by endpoint"),
+        &["endpoint"],
+    ).unwrap());
+    pub static ref TX_BROADCASTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("tx_broadcasts_total", "Signed transactions broadcast by result"),
+        &["result"],
+    ).unwrap());
+
+    pub static ref RATE_LIMITED: IntCounter = register(IntCounter::new(
+        "rate_limited_total", "Requests rejected with 429 by the rate limiter",
+    ).unwrap());
+
+    pub static ref GAS_PRICE: IntGauge = register(IntGauge::new(
+        "gas_price_wei", "Current gas price reported by the provider",
+    ).unwrap());
+    pub static ref BLOCK_HEIGHT: IntGauge = register(IntGauge::new(
+        "block_height", "Latest block number reported by the provider",
+    ).unwrap());
+}
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(metric: T) -> T {
+    REGISTRY.register(Box::new(metric.clone())).expect("Failed to register metric");
+    metric
+}
+
+pub fn observe_request<B>(res: &ServiceResponse<B>, elapsed: Duration) {
+    let req = res.request();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().as_str();
+    HTTP_REQUESTS.with_label_values(&[&route, method, res.status().as_str()]).inc();
+    HTTP_REQUEST_DURATION.with_label_values(&[&route, method]).observe(elapsed.as_secs_f64());
+}
+
+pub fn observe_rpc(method: &str, elapsed: Duration, success: bool) {
+    RPC_REQUESTS.with_label_values(&[method]).inc();
+    RPC_REQUEST_DURATION.with_label_values(&[method]).observe(elapsed.as_secs_f64());
+    if !success {
+        RPC_ERRORS.with_label_values(&[method]).inc();
+    }
+}
+
+pub fn record_tx_built(endpoint: &str) {
+    TXS_BUILT.with_label_values(&[endpoint]).inc();
+}
+
+pub fn record_broadcast(success: bool) {
+    TX_BROADCASTS.with_label_values(&[if success { "success" } else { "failure" }]).inc();
+}
+
+pub fn encode() -> Result<String, Box<dyn std::error::Error>> {
+    let mut buffer = vec![];
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
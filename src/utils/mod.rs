@@ -1,3 +1,7 @@
 
-pub mod variables;
-pub mod web3;
\ No newline at end of file
+pub mod config;
+pub mod web3;
+pub mod metrics;
+pub mod logging;
+pub mod rate_limit;
+pub mod cors;
\ No newline at end of file
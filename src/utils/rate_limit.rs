@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, StatusCode},
+    middleware::Next,
+    Error, HttpMessage, HttpResponse,
+};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::auth::api_keys::ApiClient;
+use crate::auth::middleware::read_body;
+use crate::config::{config, RateLimitConfig};
+use crate::routes::responses::{ErrorResponse, RateLimitedResponse};
+use super::metrics;
+
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+const QUOTA_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+static BUCKETS: Lazy<Mutex<HashMap<String, Bucket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static QUOTAS: Lazy<Mutex<HashMap<String, Quota>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct Bucket {
+    /// Negative after a request costing more than the whole bucket, the client then waits it off
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Quota {
+    started: Instant,
+    used: f64,
+}
+
+/// Base token cost of a request to `path`. Routes fanning out into many RPC calls cost more.
+fn route_cost(limits: &RateLimitConfig, path: &str) -> f64 {
+    if let Some(cost) = limits.route_costs.get(path) {
+        return *cost;
+    }
+    match path {
+        // storage slot probing and signature checks on top of the plain route
+        "/wallet/collectTokenWithPermit" | "/wallet/disperseTokenWithPermit" => 5.0,
+        // balance storage probing when the sender can't fund the simulated transfer
+        "/wallet/previewDisperseToken" => 10.0,
+        "/wallet/disperseToken" | "/wallet/disperseTokenByPercent" => 5.0,
+        "/wallet/collectToken" => 2.0,
+        "/wallet/disperseEther" | "/wallet/disperseEtherByPercent" | "/wallet/collectEther" | "/wallet/sendEther" => 3.0,
+        _ if path.starts_with("/token") || path.starts_with("/weth") => 2.0,
+        _ => 1.0,
+    }
+}
+
+/// Body array a request to `path` fans out over and the tokens each of its entries adds.
+fn item_cost(path: &str) -> Option<(&'static str, f64)> {
+    match path {
+        // balance + allowance per contributor
+        "/wallet/collectToken" => Some(("contributors", 2.0)),
+        // and a permit check
+        "/wallet/collectTokenWithPermit" => Some(("contributors", 3.0)),
+        // ownerOf, simulation and gas estimate per token
+        "/token721/disperse" => Some(("token_ids", 3.0)),
+        // a larger calldata to estimate
+        "/wallet/disperseToken" | "/wallet/disperseTokenByPercent" | "/wallet/disperseTokenWithPermit"
+        | "/wallet/disperseEther" | "/wallet/disperseEtherByPercent" => Some(("recipients", 0.2)),
+        _ => None,
+    }
+}
+
+/// Entries of `field` in a JSON body, zero when the body isn't JSON and the handler rejects it anyway.
+fn count_items(body: &[u8], field: &str) -> usize {
+    serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body.get(field).and_then(Value::as_array).map(Vec::len))
+        .unwrap_or(0)
+}
+
+fn client_ip(req: &ServiceRequest, limits: &RateLimitConfig) -> String {
+    let ip = if limits.trust_forwarded_for {
+        req.connection_info().realip_remote_addr().map(str::to_string)
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    };
+    ip.unwrap_or_default()
+}
+
+fn client_key(req: &ServiceRequest, limits: &RateLimitConfig) -> (String, Option<String>) {
+    if let Some(client) = req.extensions().get::<ApiClient>() {
+        return (format!("key:{}", client.id), Some(client.name.clone()));
+    }
+    (format!("ip:{}", client_ip(req, limits)), None)
+}
+
+/// Runs `f` on the client's bucket after refilling it for the time since its last use.
+fn with_bucket<T>(key: String, capacity: f64, refill_per_sec: f64, f: impl FnOnce(&mut Bucket) -> T) -> T {
+    let now = Instant::now();
+    let mut buckets = BUCKETS.lock().unwrap();
+    if buckets.len() >= MAX_TRACKED_CLIENTS {
+        let idle = Duration::from_secs_f64(capacity / refill_per_sec);
+        buckets.retain(|_, bucket| now.duration_since(bucket.updated) < idle);
+    }
+
+    let bucket = buckets.entry(key).or_insert(Bucket { tokens: capacity, updated: now });
+    let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+    bucket.updated = now;
+    f(bucket)
+}
+
+/// Takes `cost` tokens from the client's bucket, or returns how long until enough are refilled.
+/// A request costing more than the bucket holds goes through on a full bucket and leaves it in debt.
+fn take(key: String, cost: f64, capacity: f64, refill_per_sec: f64) -> Result<(), Duration> {
+    with_bucket(key, capacity, refill_per_sec, |bucket| {
+        let required = cost.min(capacity);
+        if bucket.tokens >= required {
+            bucket.tokens -= cost;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((required - bucket.tokens) / refill_per_sec))
+        }
+    })
+}
+
+/// Adds `cost` to the client's use of the current quota window, or returns how long until the
+/// window restarts when it would go over `limit`.
+fn spend_quota(key: String, cost: f64, limit: f64) -> Result<(), Duration> {
+    let now = Instant::now();
+    let mut quotas = QUOTAS.lock().unwrap();
+    if quotas.len() >= MAX_TRACKED_CLIENTS {
+        quotas.retain(|_, quota| now.duration_since(quota.started) < QUOTA_WINDOW);
+    }
+
+    let quota = quotas.entry(key).or_insert(Quota { started: now, used: 0.0 });
+    if now.duration_since(quota.started) >= QUOTA_WINDOW {
+        *quota = Quota { started: now, used: 0.0 };
+    }
+    if quota.used + cost > limit {
+        return Err(QUOTA_WINDOW - now.duration_since(quota.started));
+    }
+    quota.used += cost;
+    Ok(())
+}
+
+fn too_many_requests(req: ServiceRequest, message: &str, retry_after: Duration) -> ServiceResponse {
+    let retry_after = retry_after.as_secs() + 1;
+    metrics::RATE_LIMITED.inc();
+    let res = HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+        .json(RateLimitedResponse::new(message, retry_after));
+    req.into_response(res)
+}
+
+pub async fn rate_limit(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limits = &config().rate_limit;
+    let path = req.path().to_string();
+    if !limits.enabled || path.starts_with("/health") || path.starts_with("/metrics") {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let mut cost = route_cost(limits, &path);
+    if let Some((field, per_item)) = item_cost(&path) {
+        match read_body(&mut req).await {
+            Ok(body) => cost += per_item * count_items(&body, field) as f64,
+            Err((status, message)) => {
+                let res = HttpResponse::build(status).json(ErrorResponse::new(message));
+                return Ok(req.into_response(res).map_into_right_body());
+            },
+        }
+    }
+
+    let (key, client_name) = client_key(&req, limits);
+    let requests_per_minute = client_name.as_ref()
+        .and_then(|name| limits.clients.get(name).copied())
+        .unwrap_or(limits.requests_per_minute);
+    let capacity = limits.burst.max(1) as f64;
+    let refill_per_sec = requests_per_minute as f64 / 60.0;
+
+    if let Err(retry_after) = take(key.clone(), cost, capacity, refill_per_sec) {
+        warn!(client = %key, path = %path, cost, "Rate limit exceeded");
+        return Ok(too_many_requests(req, "Rate limit exceeded", retry_after).map_into_right_body());
+    }
+    let quota = client_name.as_ref()
+        .and_then(|name| limits.client_quotas.get(name).copied())
+        .or(limits.daily_quota);
+    if let Some(quota) = quota {
+        if let Err(retry_after) = spend_quota(key.clone(), cost, quota as f64) {
+            warn!(client = %key, path = %path, cost, quota, "Daily quota exceeded");
+            return Ok(too_many_requests(req, "Daily quota exceeded", retry_after).map_into_right_body());
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+/// Limits failed API key checks per IP. Wrapped outside `api_key_auth`, it turns an IP away once
+/// `auth_failures_per_minute` is spent, so keys can't be guessed at the full request rate, while
+/// authenticated clients sharing an address are only limited per key by [`rate_limit`].
+pub async fn auth_failure_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limits = &config().rate_limit;
+    if !limits.enabled || !config().auth.enabled {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let ip = client_ip(&req, limits);
+    let key = format!("auth:{ip}");
+    let capacity = limits.auth_failures_per_minute.max(1) as f64;
+    let refill_per_sec = capacity / 60.0;
+    let blocked = with_bucket(key.clone(), capacity, refill_per_sec, |bucket| {
+        (bucket.tokens < 1.0).then(|| Duration::from_secs_f64((1.0 - bucket.tokens) / refill_per_sec))
+    });
+    if let Some(retry_after) = blocked {
+        warn!(ip = %ip, path = req.path(), "Too many failed API key checks");
+        return Ok(too_many_requests(req, "Too many failed authentication attempts", retry_after).map_into_right_body());
+    }
+
+    let res = next.call(req).await?;
+    if res.status() == StatusCode::UNAUTHORIZED {
+        with_bucket(key, capacity, refill_per_sec, |bucket| bucket.tokens -= 1.0);
+    }
+    Ok(res.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_tokens_until_the_bucket_is_empty() {
+        let key = "test:drain".to_string();
+        assert!(take(key.clone(), 1.0, 2.0, 1.0).is_ok());
+        assert!(take(key.clone(), 1.0, 2.0, 1.0).is_ok());
+        let retry_after = take(key, 1.0, 2.0, 1.0).unwrap_err();
+        assert!(retry_after > Duration::from_millis(900) && retry_after <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn refills_at_the_configured_rate() {
+        let key = "test:refill".to_string();
+        assert!(take(key.clone(), 1.0, 1.0, 20.0).is_ok());
+        assert!(take(key.clone(), 1.0, 1.0, 20.0).is_err());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(take(key, 1.0, 1.0, 20.0).is_ok());
+    }
+
+    #[test]
+    fn oversized_requests_leave_the_bucket_in_debt() {
+        let key = "test:debt".to_string();
+        assert!(take(key.clone(), 30.0, 10.0, 1.0).is_ok());
+        // 20 tokens owed, then one more token to spend
+        let retry_after = take(key, 1.0, 10.0, 1.0).unwrap_err();
+        assert!(retry_after > Duration::from_secs(20) && retry_after <= Duration::from_secs(21));
+    }
+
+    #[test]
+    fn quota_rejects_spending_past_the_limit() {
+        let key = "test:quota".to_string();
+        assert!(spend_quota(key.clone(), 60.0, 100.0).is_ok());
+        assert!(spend_quota(key.clone(), 40.0, 100.0).is_ok());
+        let retry_after = spend_quota(key, 1.0, 100.0).unwrap_err();
+        assert!(retry_after > QUOTA_WINDOW - Duration::from_secs(60));
+    }
+
+    #[test]
+    fn scales_cost_with_the_fanned_out_array() {
+        let limits = RateLimitConfig::default();
+        let body = br#"{"token":"0x1","contributors":["0x2","0x3","0x4"],"values":["1","1","1"]}"#;
+        let (field, per_item) = item_cost("/wallet/collectToken").unwrap();
+        assert_eq!(route_cost(&limits, "/wallet/collectToken") + per_item * count_items(body, field) as f64, 8.0);
+        assert_eq!(count_items(b"not json", "contributors"), 0);
+        assert!(item_cost("/token/balanceOf").is_none());
+    }
+
+    #[test]
+    fn configured_costs_override_the_base_cost() {
+        let mut limits = RateLimitConfig::default();
+        limits.route_costs.insert("/token721/disperse".to_string(), 4.0);
+        assert_eq!(route_cost(&limits, "/token721/disperse"), 4.0);
+        assert_eq!(route_cost(&limits, "/token721/ownerOf"), 2.0);
+    }
+}
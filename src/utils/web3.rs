@@ -36,4 +36,16 @@ pub fn ether_to_string(amount: U256) -> String {
 
 pub fn parse_ether(amount: &str) -> Result<U256, Box<dyn std::error::Error>> {
     parse_u256(amount, U256::from(18))
-}
\ No newline at end of file
+}
+pub fn gwei_to_string(amount: U256) -> String {
+    u256_to_string(amount, U256::from(9))
+}
+
+pub fn parse_gwei(amount: &str) -> Result<U256, Box<dyn std::error::Error>> {
+    parse_u256(amount, U256::from(9))
+}
+
+/// Parses decimal integers without decimals, such as token ids and ERC1155 amounts.
+pub fn parse_integers(values: &[String]) -> Result<Vec<U256>, Box<dyn std::error::Error>> {
+    values.iter().map(|value| Ok(U256::from_dec_str(value)?)).collect()
+}